@@ -0,0 +1,12 @@
+create table password_reset_tokens
+(
+    id         varchar(255) not null primary key,
+    user_id    varchar(255) not null,
+    token_hash char(64)     not null unique,
+    expires_at timestamp    not null default current_timestamp,
+    used_at    timestamp    null,
+    created_at timestamp    not null default current_timestamp,
+    constraint password_reset_tokens_fk_1
+        foreign key (user_id) references users (id)
+            on delete cascade
+);
@@ -0,0 +1,25 @@
+create table user_mfa
+(
+    user_id          varchar(255) not null primary key,
+    secret_encrypted blob         not null,
+    last_used_step   bigint       null,
+    enabled_at       timestamp    null,
+    created_at       timestamp    not null default current_timestamp,
+    updated_at       timestamp    not null default current_timestamp on update current_timestamp,
+    constraint user_mfa_fk_1
+        foreign key (user_id) references users (id)
+            on delete cascade
+);
+
+create table user_mfa_recovery_codes
+(
+    id         varchar(255) not null primary key,
+    user_id    varchar(255) not null,
+    code_hash  char(64)     not null,
+    used_at    timestamp    null,
+    created_at timestamp    not null default current_timestamp,
+    constraint user_mfa_recovery_codes_fk_1
+        foreign key (user_id) references users (id)
+            on delete cascade,
+    index user_id (user_id)
+);
@@ -0,0 +1,4 @@
+alter table user_tokens
+    drop column user_agent,
+    drop column ip_address,
+    drop column last_refreshed_at;
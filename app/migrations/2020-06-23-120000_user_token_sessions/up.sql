@@ -0,0 +1,4 @@
+alter table user_tokens
+    add column user_agent varchar(512) null,
+    add column ip_address varchar(45) null,
+    add column last_refreshed_at timestamp null;
@@ -0,0 +1,7 @@
+drop table audit_events;
+
+drop table used_refresh_tokens;
+
+alter table user_tokens
+    drop index family_id,
+    drop column family_id;
@@ -0,0 +1,34 @@
+alter table user_tokens
+    add column family_id varchar(255) null;
+
+update user_tokens
+set family_id = id;
+
+alter table user_tokens
+    modify family_id varchar(255) not null,
+    add index family_id (family_id);
+
+create table used_refresh_tokens
+(
+    refresh_token_hash char(64)     not null primary key,
+    family_id          varchar(255) not null,
+    user_id            varchar(255) not null,
+    used_at            timestamp    not null default current_timestamp,
+    constraint used_refresh_tokens_fk_1
+        foreign key (user_id) references users (id)
+            on delete cascade,
+    index family_id (family_id)
+);
+
+create table audit_events
+(
+    id         varchar(255) not null primary key,
+    user_id    varchar(255) null,
+    event      varchar(64)  not null,
+    ip_address varchar(45)  null,
+    user_agent varchar(512) null,
+    details    text         null,
+    created_at timestamp    not null default current_timestamp,
+    index user_id (user_id),
+    index event (event)
+);
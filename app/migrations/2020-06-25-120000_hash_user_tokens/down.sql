@@ -0,0 +1,16 @@
+-- The plain tokens can't be recovered, the sessions stop working after reverting
+alter table user_tokens
+    add column token         mediumtext not null,
+    add column refresh_token mediumtext not null;
+
+update user_tokens
+set token         = token_hash,
+    refresh_token = refresh_token_hash;
+
+alter table user_tokens
+    drop index token_hash,
+    drop index refresh_token_hash,
+    drop column token_hash,
+    drop column refresh_token_hash,
+    add fulltext index token (token),
+    add fulltext index refresh_token (refresh_token);
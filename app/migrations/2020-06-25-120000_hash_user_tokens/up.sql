@@ -0,0 +1,17 @@
+alter table user_tokens
+    add column token_hash         char(64) null,
+    add column refresh_token_hash char(64) null;
+
+update user_tokens
+set token_hash         = sha2(token, 256),
+    refresh_token_hash = sha2(refresh_token, 256);
+
+alter table user_tokens
+    drop index token,
+    drop index refresh_token,
+    drop column token,
+    drop column refresh_token,
+    modify token_hash char(64) not null,
+    modify refresh_token_hash char(64) not null,
+    add unique index token_hash (token_hash),
+    add unique index refresh_token_hash (refresh_token_hash);
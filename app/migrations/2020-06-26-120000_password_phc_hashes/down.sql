@@ -0,0 +1,10 @@
+-- Passwords only stored as PHC strings are lost, those Users have to reset them
+update users
+set hash = '',
+    salt = ''
+where hash is null;
+
+alter table users
+    drop column password_hash,
+    modify hash blob not null,
+    modify salt varchar(255) not null;
@@ -0,0 +1,10 @@
+alter table users
+    modify hash blob null,
+    modify salt varchar(255) null,
+    add column password_hash varchar(255) null;
+
+-- Users created by an External Provider had empty values since the columns were required
+update users
+set hash = null,
+    salt = null
+where length(hash) = 0;
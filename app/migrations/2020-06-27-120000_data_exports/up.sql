@@ -0,0 +1,15 @@
+create table data_exports
+(
+    id           varchar(255) not null primary key,
+    user_id      varchar(255) not null,
+    requested_by varchar(255) not null,
+    status       varchar(16)  not null default 'pending',
+    created_at   timestamp    not null default current_timestamp,
+    completed_at timestamp    null,
+    expires_at   timestamp    null,
+    constraint data_exports_fk_1
+        foreign key (user_id) references users (id)
+            on delete cascade,
+    index requested_by (requested_by),
+    index status (status)
+);
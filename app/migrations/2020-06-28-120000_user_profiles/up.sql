@@ -0,0 +1,13 @@
+create table user_profiles
+(
+    user_id      varchar(255)  not null primary key,
+    display_name varchar(255)  null,
+    locale       varchar(35)   null,
+    timezone     varchar(64)   null,
+    avatar_url   varchar(2048) null,
+    created_at   timestamp     not null default current_timestamp,
+    updated_at   timestamp     not null default current_timestamp on update current_timestamp,
+    constraint user_profiles_fk_1
+        foreign key (user_id) references users (id)
+            on delete cascade
+);
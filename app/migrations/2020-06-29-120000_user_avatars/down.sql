@@ -0,0 +1,2 @@
+alter table user_profiles
+    drop column avatar_key;
@@ -0,0 +1,2 @@
+alter table user_profiles
+    add column avatar_key varchar(255) null;
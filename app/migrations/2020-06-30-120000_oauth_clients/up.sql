@@ -0,0 +1,11 @@
+create table oauth_clients
+(
+    id          varchar(255) not null primary key,
+    name        varchar(255) not null,
+    secret_hash char(64)     not null,
+    scopes      text         not null,
+    created_by  varchar(255) null,
+    created_at  timestamp    not null default current_timestamp,
+    updated_at  timestamp    not null default current_timestamp on update current_timestamp,
+    revoked_at  timestamp    null
+);
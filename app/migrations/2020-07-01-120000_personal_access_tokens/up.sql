@@ -0,0 +1,16 @@
+create table personal_access_tokens
+(
+    id           varchar(255) not null primary key,
+    user_id      varchar(255) not null,
+    name         varchar(255) not null,
+    token_hash   char(64)     not null,
+    scopes       text         not null,
+    expires_at   timestamp    null,
+    last_used_at timestamp    null,
+    created_at   timestamp    not null default current_timestamp,
+    revoked_at   timestamp    null,
+    constraint personal_access_tokens_fk_1
+        foreign key (user_id) references users (id)
+            on delete cascade,
+    unique index token_hash (token_hash)
+);
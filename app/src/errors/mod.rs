@@ -1,3 +1,4 @@
+use actix_web::error::BlockingError;
 use async_graphql::FieldError;
 use diesel::result::{DatabaseErrorKind, Error as DBError};
 use serde_json::json;
@@ -13,6 +14,8 @@ pub enum SrvError {
     Duplicate(DuplicateErrorInfo),
     ValidationError(ValidationErrors),
     Unavailable,
+    /// Too many failed attempts, with the seconds until it can be tried again
+    TooManyAttempts(u64),
 }
 
 #[derive(Debug)]
@@ -45,6 +48,10 @@ impl From<SrvError> for FieldError {
                 "UNAVAILABLE",
                 json!({ "info": "This functionality is not available yet!" }),
             ),
+            TooManyAttempts(retry_after) => (
+                "TOO_MANY_ATTEMPTS",
+                json!({ "info": "Too many failed attempts, try again later!", "retryAfter": retry_after }),
+            ),
         };
         FieldError(title.to_string(), Some(extensions))
     }
@@ -78,3 +85,26 @@ impl From<validator::ValidationErrors> for SrvError {
         SrvError::ValidationError(error)
     }
 }
+
+impl From<reqwest::Error> for SrvError {
+    fn from(error: reqwest::Error) -> SrvError {
+        println!("{:?}", error);
+        SrvError::InternalServerError
+    }
+}
+
+impl From<BlockingError<SrvError>> for SrvError {
+    fn from(error: BlockingError<SrvError>) -> SrvError {
+        match error {
+            BlockingError::Error(error) => error,
+            BlockingError::Canceled => SrvError::InternalServerError,
+        }
+    }
+}
+
+impl From<redis::RedisError> for SrvError {
+    fn from(error: redis::RedisError) -> SrvError {
+        println!("{:?}", error);
+        SrvError::InternalServerError
+    }
+}
@@ -1,8 +1,17 @@
 use crate::db::mysql::{DbPool, DbPooledConnection};
 use crate::graphql::dataloaders::DataLoaders;
+use crate::graphql::guards::Role;
 use crate::graphql::utils::authorization::AuthorizationService;
-use crate::models::{AuthAssignmentModel as AuthAssignment, UserModel as User};
-use crate::web_utils::jwt::LoggedUser;
+use crate::mail::ArcMailService;
+use crate::models::{
+    AuthAssignmentModel as AuthAssignment, PersonalAccessTokenModel as PersonalAccessToken, Scope,
+    UserModel as User,
+};
+use crate::storage::ArcBlobStore;
+use crate::utils::revocation;
+use crate::web_utils::jwt::{ClientPrincipal, LoggedUser, Principal};
+use crate::web_utils::request::RequestInfo;
+use once_cell::sync::OnceCell;
 use std::sync::Arc;
 
 pub type ArcDbPool = Arc<DbPool>;
@@ -11,38 +20,183 @@ pub type ArcRedisClient = Arc<redis::Client>;
 pub struct Context {
     pub pool: ArcDbPool,
     pub redis_client: ArcRedisClient,
+    pub mail_service: ArcMailService,
+    pub blob_store: ArcBlobStore,
     pub dataloaders: DataLoaders,
-    pub user: Option<User>,
-    pub user_token: Option<String>,
-    pub user_assignments: Option<Vec<AuthAssignment>>,
     pub auth_service: AuthorizationService,
+    pub request_info: RequestInfo,
+    user_info: LoggedUser,
+    client_info: Option<ClientPrincipal>,
+    access_token: Option<String>,
+    // Filled on the first use, anonymous operations never touch Redis or the database
+    authenticated: OnceCell<bool>,
+    client_authenticated: OnceCell<bool>,
+    personal_access_token: OnceCell<Option<PersonalAccessToken>>,
+    user: OnceCell<Option<User>>,
+    user_assignments: OnceCell<Option<Vec<AuthAssignment>>>,
 }
 
 impl Context {
-    pub fn new(user_info: LoggedUser, pool: ArcDbPool, redis_client: ArcRedisClient) -> Context {
-        let mut auth_service = AuthorizationService::new(Arc::clone(&pool));
-        auth_service
-            .init()
-            .expect("Error in AuthorizationService Initialization");
-        let (user, user_assignments) = {
-            let conn: &DbPooledConnection = &pool.get().unwrap();
-            let user = User::find_user(&user_info, conn);
-            let user_assignments = {
-                match &user {
-                    Some(user) => user.auth_assignments(conn).ok(),
-                    None => None,
-                }
-            };
-            (user, user_assignments)
+    pub fn new(
+        principal: Principal,
+        pool: ArcDbPool,
+        redis_client: ArcRedisClient,
+        mail_service: ArcMailService,
+        blob_store: ArcBlobStore,
+        auth_service: AuthorizationService,
+        request_info: RequestInfo,
+    ) -> Context {
+        let (user_info, client_info, access_token) = match principal {
+            Principal::User(user_info) => (user_info, None, None),
+            Principal::Client(client_info) => (LoggedUser::default(), Some(client_info), None),
+            Principal::AccessToken(token) => (LoggedUser::default(), None, Some(token)),
+            Principal::Anonymous => (LoggedUser::default(), None, None),
         };
         Context {
             dataloaders: DataLoaders::new(pool.clone()),
             pool,
             redis_client,
-            user,
-            user_token: user_info.token,
-            user_assignments,
+            mail_service,
+            blob_store,
             auth_service,
+            request_info,
+            user_info,
+            client_info,
+            access_token,
+            authenticated: OnceCell::new(),
+            client_authenticated: OnceCell::new(),
+            personal_access_token: OnceCell::new(),
+            user: OnceCell::new(),
+            user_assignments: OnceCell::new(),
         }
     }
+
+    /// If the request has a valid access token of a session that was not revoked
+    fn is_authenticated(&self) -> bool {
+        *self.authenticated.get_or_init(|| {
+            match (&self.user_info.id, &self.user_info.session_id) {
+                (Some(_), Some(session_id)) => {
+                    // Without Redis the revocations can't be checked, so the token is refused
+                    revocation::is_revoked(&self.redis_client, session_id)
+                        .map_or(false, |revoked| !revoked)
+                }
+                _ => false,
+            }
+        })
+    }
+
+    /// The id of the authenticated User, known from the token claims only when it has a session
+    pub fn user_id(&self) -> Option<&String> {
+        self.session_user_id().or_else(|| {
+            self.personal_access_token()
+                .map(|access_token| &access_token.user_id)
+        })
+    }
+
+    /// The id of the User authenticated with a session, the personal access tokens are excluded
+    /// because their scopes don't cover the account data such as the sessions
+    pub fn session_user_id(&self) -> Option<&String> {
+        self.user_info
+            .id
+            .as_ref()
+            .filter(|_| self.is_authenticated())
+    }
+
+    /// The admin impersonating the User of this request
+    pub fn impersonator_id(&self) -> Option<&String> {
+        self.user_info
+            .actor_id
+            .as_ref()
+            .filter(|_| self.is_authenticated())
+    }
+
+    /// The id of the User really acting in this request, the admin when impersonating a User
+    pub fn actor_id(&self) -> Option<&String> {
+        self.impersonator_id().or_else(|| self.user_id())
+    }
+
+    /// The session of the access token used by this request
+    pub fn session_id(&self) -> Option<&String> {
+        self.user_info
+            .session_id
+            .as_ref()
+            .filter(|_| self.is_authenticated())
+    }
+
+    /// The authenticated User, loaded from the database the first time it is needed
+    pub fn user(&self) -> &Option<User> {
+        self.user.get_or_init(|| {
+            let user_id = self.user_id()?;
+            let conn: &DbPooledConnection = &self.pool.get().unwrap();
+            User::find(user_id, conn).ok().flatten()
+        })
+    }
+
+    /// The OAuth2 client of the request, when its token was not revoked
+    pub fn client(&self) -> Option<&ClientPrincipal> {
+        self.client_info.as_ref().filter(|client| {
+            *self.client_authenticated.get_or_init(|| {
+                revocation::is_revoked(&self.redis_client, &client.client_id)
+                    .map_or(false, |revoked| !revoked)
+            })
+        })
+    }
+
+    /// The personal access token of the request, when it is active and its User was not deleted
+    pub fn personal_access_token(&self) -> Option<&PersonalAccessToken> {
+        self.personal_access_token
+            .get_or_init(|| {
+                let token = self.access_token.as_ref()?;
+                let conn: &DbPooledConnection = &self.pool.get().unwrap();
+                let access_token = PersonalAccessToken::find_active(token, conn).ok()??;
+                User::find(&access_token.user_id, conn).ok()??;
+                access_token.touch(conn).ok();
+                Some(access_token)
+            })
+            .as_ref()
+    }
+
+    /// If the request may use what is restricted to the role, the OAuth2 clients need the scope
+    /// instead and the personal access tokens need both
+    pub fn is_authorized(&self, role: &Role, scope: Option<Scope>) -> bool {
+        if let Some(client) = self.client() {
+            return scope.map_or(false, |scope| client.scopes.contains(&scope));
+        }
+        let in_scope = self.personal_access_token().map_or(true, |access_token| {
+            scope.map_or(false, |scope| {
+                access_token.allowed_scopes().contains(&scope)
+            })
+        });
+        in_scope
+            && match role {
+                Role::User => self.auth_service.is_user(self.user_assignments()),
+                Role::Admin => self.auth_service.is_admin(self.user_assignments()),
+            }
+    }
+
+    /// Who is acting in this request, as written in the audit events
+    pub fn actor(&self) -> Option<String> {
+        if let (Some(user_id), Some(impersonator_id)) = (self.user_id(), self.impersonator_id()) {
+            return Some(format!(
+                "admin {} impersonating user {}",
+                impersonator_id, user_id
+            ));
+        }
+        match (self.user_id(), self.client(), self.personal_access_token()) {
+            (Some(user_id), _, Some(access_token)) => {
+                Some(format!("user {} with token {}", user_id, access_token.id))
+            }
+            (Some(user_id), _, None) => Some(format!("user {}", user_id)),
+            (None, Some(client), _) => Some(format!("client {}", client.client_id)),
+            (None, None, _) => None,
+        }
+    }
+
+    pub fn user_assignments(&self) -> &Option<Vec<AuthAssignment>> {
+        self.user_assignments.get_or_init(|| {
+            let user = self.user().as_ref()?;
+            let conn: &DbPooledConnection = &self.pool.get().unwrap();
+            user.auth_assignments(conn).ok()
+        })
+    }
 }
@@ -27,6 +27,7 @@ impl BatchFn<String, Vec<ExternalUserProvider>> for EupByUserIdLoaderFn {
         let data: Vec<ExternalUserProvider> = {
             match external_user_providers
                 .filter(user_id.eq_any(keys))
+                .filter(deleted.eq(false))
                 .load::<ExternalUserProvider>(conn)
             {
                 Ok(r) => r,
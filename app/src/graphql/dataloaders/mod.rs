@@ -1,20 +1,24 @@
 pub mod external_user_provider;
+pub mod user_profile;
 
 use crate::graphql::context::ArcDbPool;
 use dataloader::cached::Loader;
 use external_user_provider::{EupByUserId, EupByUserIdLoaderFn};
 use std::collections::HashMap;
+use user_profile::{ProfileByUserId, ProfileByUserIdLoaderFn};
 
 type CachedDataLoader<K, V, B> = Loader<K, V, B, HashMap<K, V>>;
 
 pub struct DataLoaders {
     pub e_user_by_user_id: EupByUserId,
+    pub profile_by_user_id: ProfileByUserId,
 }
 
 impl DataLoaders {
     pub fn new(pool: ArcDbPool) -> Self {
         Self {
             e_user_by_user_id: Loader::new(EupByUserIdLoaderFn::new(pool.clone())),
+            profile_by_user_id: Loader::new(ProfileByUserIdLoaderFn::new(pool.clone())),
         }
     }
 }
@@ -0,0 +1,42 @@
+use crate::graphql::context::ArcDbPool;
+use crate::graphql::dataloaders::CachedDataLoader;
+use crate::graphql::objects::user_profile::UserProfile;
+use async_trait::async_trait;
+use dataloader::BatchFn;
+use diesel::prelude::*;
+use std::collections::HashMap;
+
+pub type ProfileByUserId = CachedDataLoader<String, Option<UserProfile>, ProfileByUserIdLoaderFn>;
+
+pub struct ProfileByUserIdLoaderFn {
+    pub db: ArcDbPool,
+}
+
+impl ProfileByUserIdLoaderFn {
+    pub fn new(db: ArcDbPool) -> Self {
+        Self { db }
+    }
+}
+
+#[async_trait]
+impl BatchFn<String, Option<UserProfile>> for ProfileByUserIdLoaderFn {
+    async fn load(&self, keys: &[String]) -> HashMap<String, Option<UserProfile>> {
+        use crate::schema::user_profiles::dsl::*;
+        let conn: &MysqlConnection = &self.db.get().unwrap();
+        let data: Vec<UserProfile> = {
+            match user_profiles
+                .filter(user_id.eq_any(keys))
+                .load::<UserProfile>(conn)
+            {
+                Ok(r) => r,
+                Err(_) => Vec::new(),
+            }
+        };
+        let mapped_data: HashMap<String, Option<UserProfile>> =
+            keys.iter().map(|v| (v.clone(), None)).collect();
+        data.into_iter().fold(mapped_data, |mut acc, val| {
+            acc.insert(val.user_id.clone(), Some(val));
+            acc
+        })
+    }
+}
@@ -1,7 +1,10 @@
 use crate::errors::{SrvError, UnauthorizedInfo};
 use crate::graphql::context::Context;
+use crate::graphql::utils::audit;
 use async_graphql::{guard::Guard, Context as GqlContext, FieldResult};
 
+pub use crate::models::Scope;
+
 #[derive(Debug)]
 pub enum Role {
     Admin,
@@ -13,25 +16,53 @@ pub struct RoleGuard {
     pub role: Role,
 }
 
+/// Allows the Users with a session, the tokens without a session are refused
 #[derive(Debug)]
 pub struct AuthGuard {}
 
+/// Allows the Users with a session, unless an admin is impersonating them. For the mutations that
+/// manage the account itself, such as its credentials, which an admin must never change
+#[derive(Debug)]
+pub struct AccountGuard {}
+
+/// Stores the mutations done while an admin impersonates a User in the audit log before they run,
+/// every mutation has it unless it is refused by [`AccountGuard`]
+#[derive(Debug)]
+pub struct ImpersonationGuard {}
+
+/// Allows the Users with the role and the tokens with the scope, see [`Context::is_authorized`]
+#[derive(Debug)]
+pub struct AccessGuard {
+    pub role: Role,
+    pub scope: Scope,
+}
+
+fn unauthorized() -> SrvError {
+    SrvError::Unauthorized(UnauthorizedInfo {
+        data: String::from("You are not Authorized to acess This!"),
+    })
+}
+
 #[async_trait::async_trait]
 impl Guard for RoleGuard {
     async fn check(&self, ctx: &GqlContext<'_>) -> FieldResult<()> {
         let context = ctx.data::<Context>();
-        let auth_service = &context.auth_service;
-        let is_authorized = match &self.role {
-            Role::User => auth_service.is_user(&context.user_assignments),
-            Role::Admin => auth_service.is_admin(&context.user_assignments),
-        };
-        if is_authorized {
+        if context.is_authorized(&self.role, None) {
             Ok(())
         } else {
-            Err(SrvError::Unauthorized(UnauthorizedInfo {
-                data: String::from("You are not Authorized to acess This!"),
-            })
-            .into())
+            Err(unauthorized().into())
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl Guard for AccessGuard {
+    async fn check(&self, ctx: &GqlContext<'_>) -> FieldResult<()> {
+        let context = ctx.data::<Context>();
+        if context.is_authorized(&self.role, Some(self.scope)) {
+            Ok(())
+        } else {
+            Err(unauthorized().into())
         }
     }
 }
@@ -40,7 +71,7 @@ impl Guard for RoleGuard {
 impl Guard for AuthGuard {
     async fn check(&self, ctx: &GqlContext<'_>) -> FieldResult<()> {
         let context = ctx.data::<Context>();
-        if context.user.is_some() {
+        if context.session_id().is_some() {
             Ok(())
         } else {
             Err(SrvError::Unauthorized(UnauthorizedInfo {
@@ -50,3 +81,29 @@ impl Guard for AuthGuard {
         }
     }
 }
+
+#[async_trait::async_trait]
+impl Guard for AccountGuard {
+    async fn check(&self, ctx: &GqlContext<'_>) -> FieldResult<()> {
+        AuthGuard {}.check(ctx).await?;
+        if ctx.data::<Context>().impersonator_id().is_some() {
+            return Err(SrvError::Unauthorized(UnauthorizedInfo {
+                data: String::from("This can't be done while impersonating a User!"),
+            })
+            .into());
+        }
+        Ok(())
+    }
+}
+
+#[async_trait::async_trait]
+impl Guard for ImpersonationGuard {
+    async fn check(&self, ctx: &GqlContext<'_>) -> FieldResult<()> {
+        // The name of the field and not its alias, so the mutation can't be hidden
+        let mutation = ctx.item.name.as_str();
+        Ok(audit::record_impersonated_mutation(
+            ctx.data::<Context>(),
+            mutation,
+        )?)
+    }
+}
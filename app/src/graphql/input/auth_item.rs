@@ -0,0 +1,39 @@
+use crate::graphql::objects::auth_item::AuthItemType;
+use crate::models::{AuthItemType as ItemType, UpdatedAuthItemModel as UpdatedAuthItem};
+use async_graphql::InputObject;
+use validator::Validate;
+
+#[InputObject]
+#[derive(Debug, Validate)]
+/// A role or a permission to be created
+pub struct AuthItemInput {
+    #[validate(length(min = 1, max = 64))]
+    pub name: String,
+    #[field(name = "type")]
+    pub kind: AuthItemType,
+    pub description: Option<String>,
+}
+
+#[InputObject]
+#[derive(Debug, Validate)]
+/// Fields of a role or a permission to be Updated, an empty description removes it
+pub struct AuthItemUpdateInput {
+    /// The new name, the assignments and the hierarchy follow it
+    #[validate(length(min = 1, max = 64))]
+    pub name: Option<String>,
+    #[field(name = "type")]
+    pub kind: Option<AuthItemType>,
+    pub description: Option<String>,
+}
+
+impl AuthItemUpdateInput {
+    pub fn changes(self) -> UpdatedAuthItem {
+        UpdatedAuthItem {
+            name: self.name,
+            type_: self.kind.map(|kind| ItemType::from(kind).as_i16()),
+            description: self
+                .description
+                .map(|description| Some(description).filter(|d| !d.is_empty())),
+        }
+    }
+}
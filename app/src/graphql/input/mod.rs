@@ -1,2 +1,8 @@
+pub mod auth_item;
+pub mod oauth_client;
+pub mod personal_access_token;
 pub mod user;
+pub use auth_item::*;
+pub use oauth_client::*;
+pub use personal_access_token::*;
 pub use user::*;
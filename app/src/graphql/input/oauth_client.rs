@@ -0,0 +1,35 @@
+use crate::models::Scope;
+use async_graphql::InputObject;
+use std::borrow::Cow;
+use validator::{Validate, ValidationError};
+
+/// Only the scopes known by [`Scope`], shared by the inputs of every kind of token
+pub fn validate_scopes(scopes: &[String]) -> Result<(), ValidationError> {
+    if scopes.iter().all(|scope| Scope::from_name(scope).is_some()) {
+        Ok(())
+    } else {
+        let mut error = ValidationError::new("scope");
+        error.message = Some(Cow::from("This value should only have known scopes"));
+        Err(error)
+    }
+}
+
+#[InputObject]
+#[derive(Debug, Deserialize, Validate)]
+/// A service to be registered as an OAuth2 client
+pub struct OAuthClientInput {
+    #[validate(length(min = 1, max = 255))]
+    pub name: String,
+    /// Scopes such as `users:read` that the client can request
+    #[validate(length(min = 1), custom = "validate_scopes")]
+    pub scopes: Vec<String>,
+}
+
+impl OAuthClientInput {
+    pub fn scopes(&self) -> Vec<Scope> {
+        self.scopes
+            .iter()
+            .filter_map(|scope| Scope::from_name(scope))
+            .collect()
+    }
+}
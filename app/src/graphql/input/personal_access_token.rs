@@ -0,0 +1,40 @@
+use crate::graphql::input::oauth_client::validate_scopes;
+use crate::models::Scope;
+use async_graphql::InputObject;
+use chrono::*;
+use std::borrow::Cow;
+use validator::{Validate, ValidationError};
+
+fn validate_expires_at(expires_at: &DateTime<Utc>) -> Result<(), ValidationError> {
+    if *expires_at > Utc::now() {
+        Ok(())
+    } else {
+        let mut error = ValidationError::new("expires_at");
+        error.message = Some(Cow::from("This value should be in the future"));
+        Err(error)
+    }
+}
+
+#[InputObject]
+#[derive(Debug, Deserialize, Validate)]
+/// A personal access token to be created for the authenticated User
+pub struct PersonalAccessTokenInput {
+    /// Tells the tokens apart, such as the script that uses it
+    #[validate(length(min = 1, max = 255))]
+    pub name: String,
+    /// Scopes such as `profile:read`, the token never allows more than the roles of the User
+    #[validate(length(min = 1), custom = "validate_scopes")]
+    pub scopes: Vec<String>,
+    /// Without it the token is valid until it is revoked
+    #[validate(custom = "validate_expires_at")]
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+impl PersonalAccessTokenInput {
+    pub fn scopes(&self) -> Vec<Scope> {
+        self.scopes
+            .iter()
+            .filter_map(|scope| Scope::from_name(scope))
+            .collect()
+    }
+}
@@ -1,4 +1,6 @@
 use crate::graphql::objects::external_user_provider::UserProvider;
+use crate::models::UpdatedUserProfileModel as UpdatedUserProfile;
+use crate::utils::profile::{validate_avatar_url, validate_locale, validate_timezone};
 use async_graphql::InputObject;
 use validator::Validate;
 
@@ -9,7 +11,7 @@ pub struct UserRegisterInput {
     pub email: String,
     #[validate(length(min = 1, max = 255))]
     pub name: String,
-    #[validate(length(min = 6))]
+    /// Checked by the password policy
     pub password: String,
 }
 
@@ -32,12 +34,53 @@ pub struct UserLoginInput {
 
 #[InputObject]
 #[derive(Debug, Deserialize, Validate)]
-/// User Data to be Updated
+/// User Data to be Updated, the profile fields are changed by `updateProfile`
 pub struct UserUpdateInput {
     #[validate(email(message = "This value should be a E-Mail"))]
     /// Changed E-mail
     pub email: Option<String>,
-    #[validate(length(min = 6))]
-    /// Changed password
+    /// Changed password, checked by the password policy
     pub password: Option<String>,
 }
+
+#[InputObject]
+#[derive(Debug, Deserialize, Validate)]
+/// Profile fields to be Updated, an empty value removes the field
+pub struct UserProfileInput {
+    #[validate(length(max = 255))]
+    pub display_name: Option<String>,
+    /// A BCP 47 language tag such as `pt-BR`
+    #[validate(custom = "validate_locale")]
+    pub locale: Option<String>,
+    /// A IANA time zone such as `America/Sao_Paulo`
+    #[validate(custom = "validate_timezone")]
+    pub timezone: Option<String>,
+    /// Replaces the uploaded avatar
+    #[validate(custom = "validate_avatar_url")]
+    pub avatar_url: Option<String>,
+}
+
+impl UserProfileInput {
+    pub fn changes(self) -> UpdatedUserProfile {
+        let change =
+            |value: Option<String>| value.map(|value| Some(value).filter(|v| !v.is_empty()));
+        UpdatedUserProfile {
+            // A new avatar URL replaces the uploaded avatar
+            avatar_key: self.avatar_url.as_ref().map(|_| None),
+            display_name: change(self.display_name),
+            locale: change(self.locale),
+            timezone: change(self.timezone),
+            avatar_url: change(self.avatar_url),
+        }
+    }
+}
+
+#[InputObject]
+#[derive(Debug, Deserialize, Validate)]
+/// A Password Reset using the token sent by E-mail
+pub struct PasswordResetInput {
+    /// Token received by E-mail
+    pub token: String,
+    /// The new password, checked by the password policy
+    pub new_password: String,
+}
@@ -0,0 +1,170 @@
+use crate::errors::SrvError;
+use crate::graphql::context::Context;
+use crate::graphql::input::{AuthItemInput, AuthItemUpdateInput};
+use crate::graphql::objects::auth_item::AuthItem;
+use crate::graphql::utils::audit;
+use crate::graphql::utils::authorization::{ADMIN_ITEM_NAME, USER_ITEM_NAME};
+use crate::models::{
+    AuditEvent, AuthItemChildModel as AuthItemChild, NewAuthItemChildModel as NewAuthItemChild,
+    NewAuthItemModel as NewAuthItem,
+};
+use async_graphql::Context as GqlContext;
+use diesel::prelude::*;
+use std::borrow::Cow;
+use validator::{Validate, ValidationError, ValidationErrors};
+
+fn invalid(field: &'static str, code: &'static str, message: &'static str) -> SrvError {
+    let mut error = ValidationError::new(code);
+    error.message = Some(Cow::from(message));
+    let mut errors = ValidationErrors::new();
+    errors.add(field, error);
+    SrvError::ValidationError(errors)
+}
+
+/// The roles checked by the guards, renaming or deleting them would lock everyone out
+fn assert_not_builtin(name: &str) -> Result<(), SrvError> {
+    if name == ADMIN_ITEM_NAME || name == USER_ITEM_NAME {
+        return Err(invalid(
+            "name",
+            "builtin",
+            "The admin and user roles can't be renamed or deleted",
+        ));
+    }
+    Ok(())
+}
+
+/// Runs a change of the items in a transaction with its audit event, then reloads the items of
+/// every server
+fn change_items<T, F>(context: &Context, details: String, change: F) -> Result<T, SrvError>
+where
+    F: FnOnce(&MysqlConnection) -> Result<T, SrvError>,
+{
+    let conn: &MysqlConnection = &context.pool.get().unwrap();
+    let result = conn.transaction::<_, SrvError, _>(|| {
+        let result = change(conn)?;
+        let details = Some(format!(
+            "{}, by {}",
+            details,
+            context.actor().unwrap_or_default()
+        ));
+        audit::record(context, AuditEvent::AuthItemsChanged, None, details, conn)?;
+        Ok(result)
+    })?;
+    // The change is already saved, so a Redis failure only delays the reload of the other servers
+    if let Err(error) = context.auth_service.changed(&context.redis_client) {
+        println!("{:?}", error);
+    }
+    Ok(result)
+}
+
+/// Creates a role or a permission
+///
+/// # Arguments
+/// * `ctx` - The GraphQL Context
+/// * `input` - The name, the type and the description of the item
+pub fn create_auth_item(ctx: &GqlContext<'_>, input: AuthItemInput) -> Result<AuthItem, SrvError> {
+    let context = ctx.data::<Context>();
+    input.validate()?;
+    let details = format!("created: {}", input.name);
+    change_items(context, details, |conn| {
+        Ok(NewAuthItem::new(
+            &input.name,
+            input.kind.into(),
+            input.description.as_deref().filter(|d| !d.is_empty()),
+        )
+        .save(conn)?)
+    })
+}
+
+/// Updates a role or a permission
+///
+/// # Arguments
+/// * `ctx` - The GraphQL Context
+/// * `name` - The current name of the item
+/// * `input` - The fields that will be Updated
+pub fn update_auth_item(
+    ctx: &GqlContext<'_>,
+    name: String,
+    input: AuthItemUpdateInput,
+) -> Result<AuthItem, SrvError> {
+    let context = ctx.data::<Context>();
+    input.validate()?;
+    if input
+        .name
+        .as_ref()
+        .map_or(false, |new_name| new_name != &name)
+    {
+        assert_not_builtin(&name)?;
+    }
+    let details = format!("updated: {}", name);
+    change_items(context, details, |conn| {
+        let item = AuthItem::find(&name, conn)?.ok_or(SrvError::NotFound)?;
+        Ok(input.changes().update(&item, conn)?)
+    })
+}
+
+/// Deletes a role or a permission together with its edges and assignments
+///
+/// # Arguments
+/// * `ctx` - The GraphQL Context
+/// * `name` - The name of the item
+pub fn delete_auth_item(ctx: &GqlContext<'_>, name: String) -> Result<bool, SrvError> {
+    let context = ctx.data::<Context>();
+    assert_not_builtin(&name)?;
+    let details = format!("deleted: {}", name);
+    change_items(context, details, |conn| {
+        let item = AuthItem::find(&name, conn)?.ok_or(SrvError::NotFound)?;
+        item.delete(conn)?;
+        Ok(true)
+    })
+}
+
+/// Makes `child` a child of `parent`, so every User with `parent` is also granted `child`. Edges
+/// that would create a cycle are refused
+///
+/// # Arguments
+/// * `ctx` - The GraphQL Context
+/// * `parent` - The name of the parent item
+/// * `child` - The name of the child item
+pub fn add_auth_item_child(
+    ctx: &GqlContext<'_>,
+    parent: String,
+    child: String,
+) -> Result<AuthItem, SrvError> {
+    let context = ctx.data::<Context>();
+    let details = format!("added child: {} -> {}", parent, child);
+    change_items(context, details, |conn| {
+        let parent_item = AuthItem::find(&parent, conn)?.ok_or(SrvError::NotFound)?;
+        AuthItem::find(&child, conn)?.ok_or(SrvError::NotFound)?;
+        // Locks the edges until the insert is committed, so a concurrent edge can't close a cycle
+        if AuthItemChild::reaches(&child, &parent, conn)? {
+            return Err(invalid(
+                "child",
+                "cycle",
+                "This item already grants the parent, the edge would create a cycle",
+            ));
+        }
+        if AuthItemChild::find(&parent, &child, conn)?.is_none() {
+            NewAuthItemChild::new(&parent, &child).save(conn)?;
+        }
+        Ok(parent_item)
+    })
+}
+
+/// Removes `child` from the children of `parent`
+///
+/// # Arguments
+/// * `ctx` - The GraphQL Context
+/// * `parent` - The name of the parent item
+/// * `child` - The name of the child item
+pub fn remove_auth_item_child(
+    ctx: &GqlContext<'_>,
+    parent: String,
+    child: String,
+) -> Result<bool, SrvError> {
+    let context = ctx.data::<Context>();
+    let details = format!("removed child: {} -> {}", parent, child);
+    change_items(context, details, |conn| {
+        Ok(AuthItemChild::delete(&parent, &child, conn)? > 0)
+    })
+}
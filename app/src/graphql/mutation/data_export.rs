@@ -0,0 +1,62 @@
+use crate::errors::SrvError;
+use crate::graphql::context::Context;
+use crate::graphql::objects::data_export::DataExport;
+use crate::graphql::utils::audit;
+use crate::graphql::utils::authorization::assert_user;
+use crate::jobs;
+use crate::models::{AuditEvent, NewDataExportModel as NewDataExport, UserModel as User};
+use async_graphql::{Context as GqlContext, ID};
+use diesel::prelude::*;
+
+/// Creates an export of a User and builds its archive in the background, an export that is still
+/// pending for the same requester is returned instead of starting another one
+fn start_export(
+    context: &Context,
+    user_id: &str,
+    requester_id: &str,
+) -> Result<DataExport, SrvError> {
+    let conn: &MysqlConnection = &context.pool.get().unwrap();
+    if let Some(export) = DataExport::find_pending(user_id, requester_id, conn)? {
+        return Ok(export);
+    }
+    let export = conn.transaction::<_, SrvError, _>(|| {
+        let export = NewDataExport::new(user_id, requester_id).save(conn)?;
+        let details = Some(format!(
+            "export: {}, requested by: {}",
+            export.id, requester_id
+        ));
+        audit::record(
+            context,
+            AuditEvent::DataExportRequested,
+            Some(user_id),
+            details,
+            conn,
+        )?;
+        Ok(export)
+    })?;
+    jobs::data_export::spawn(context.pool.clone(), export.clone());
+    Ok(export)
+}
+
+/// Exports all the personal data of the authenticated User
+///
+/// # Arguments
+/// * `ctx` - The GraphQL Context
+pub fn request_data_export(ctx: &GqlContext<'_>) -> Result<DataExport, SrvError> {
+    let context = ctx.data::<Context>();
+    let user = assert_user(context.user())?;
+    start_export(context, &user.id, &user.id)
+}
+
+/// Exports all the personal data of any User, including the deleted ones
+///
+/// # Arguments
+/// * `ctx` - The GraphQL Context
+/// * `user_id` - The id of the User
+pub fn request_user_data_export(ctx: &GqlContext<'_>, user_id: ID) -> Result<DataExport, SrvError> {
+    let context = ctx.data::<Context>();
+    let admin = assert_user(context.user())?;
+    let conn: &MysqlConnection = &context.pool.get().unwrap();
+    let user = User::find_with_deleted(&user_id, conn)?.ok_or(SrvError::NotFound)?;
+    start_export(context, &user.id, &admin.id)
+}
@@ -0,0 +1,101 @@
+use crate::errors::{SrvError, UnauthorizedInfo};
+use crate::graphql::context::Context;
+use crate::graphql::objects::impersonation::Impersonation;
+use crate::graphql::utils::audit;
+use crate::models::{AuditEvent, UserModel as User};
+use crate::utils::revocation;
+use crate::web_utils::jwt::{create_impersonation_token, IMPERSONATION_DURATION_IN_MINUTES};
+use async_graphql::{Context as GqlContext, ID};
+use chrono::*;
+use cuid::cuid;
+use diesel::prelude::*;
+use std::borrow::Cow;
+use validator::{ValidationError, ValidationErrors};
+
+const REASON_MAX_LENGTH: usize = 500;
+
+fn invalid_reason() -> SrvError {
+    let mut error = ValidationError::new("length");
+    error.message = Some(Cow::from(format!(
+        "This value should have between 1 and {} characters",
+        REASON_MAX_LENGTH
+    )));
+    let mut errors = ValidationErrors::new();
+    errors.add("reason", error);
+    SrvError::ValidationError(errors)
+}
+
+/// Issues a token that acts as a User for the authenticated admin, other admins can't be
+/// impersonated
+///
+/// # Arguments
+/// * `ctx` - The GraphQL Context
+/// * `user_id` - The id of the impersonated User
+/// * `reason` - Why the User is impersonated, stored in the audit log
+pub fn impersonate_user(
+    ctx: &GqlContext<'_>,
+    user_id: ID,
+    reason: String,
+) -> Result<Impersonation, SrvError> {
+    let context = ctx.data::<Context>();
+    let admin_id = context.user_id().ok_or(SrvError::InternalServerError)?;
+    let reason = reason.trim();
+    if reason.is_empty() || reason.chars().count() > REASON_MAX_LENGTH {
+        return Err(invalid_reason());
+    }
+    let conn: &MysqlConnection = &context.pool.get().unwrap();
+    let user = User::find(&user_id, conn)?.ok_or(SrvError::NotFound)?;
+    if context
+        .auth_service
+        .is_admin(&Some(user.auth_assignments(conn)?))
+    {
+        return Err(SrvError::Unauthorized(UnauthorizedInfo {
+            data: String::from("Admins can't be impersonated!"),
+        }));
+    }
+    let impersonation_id = cuid().unwrap();
+    let token = create_impersonation_token(&user.id, &impersonation_id, admin_id)
+        .ok_or(SrvError::InternalServerError)?;
+    let details = Some(format!(
+        "impersonation: {}, by admin: {}, reason: {}",
+        impersonation_id, admin_id, reason
+    ));
+    audit::record(
+        context,
+        AuditEvent::ImpersonationStarted,
+        Some(&user.id),
+        details,
+        conn,
+    )?;
+    Ok(Impersonation {
+        token,
+        expires_at: Utc::now() + Duration::minutes(IMPERSONATION_DURATION_IN_MINUTES),
+        user,
+    })
+}
+
+/// Revokes the impersonation token of the request
+///
+/// # Arguments
+/// * `ctx` - The GraphQL Context
+pub fn end_impersonation(ctx: &GqlContext<'_>) -> Result<bool, SrvError> {
+    let context = ctx.data::<Context>();
+    let impersonator_id = match context.impersonator_id() {
+        Some(impersonator_id) => impersonator_id,
+        None => return Ok(false),
+    };
+    let impersonation_id = context.session_id().unwrap();
+    let conn: &MysqlConnection = &context.pool.get().unwrap();
+    audit::record(
+        context,
+        AuditEvent::ImpersonationEnded,
+        context.user_id().map(|id| id.as_str()),
+        Some(format!(
+            "impersonation: {}, by admin: {}",
+            impersonation_id, impersonator_id
+        )),
+        conn,
+    )?;
+    revocation::revoke_sessions(&context.redis_client, &[impersonation_id.clone()])?;
+    Ok(true)
+}
@@ -0,0 +1,111 @@
+use crate::errors::{SrvError, UnauthorizedInfo};
+use crate::graphql::context::Context;
+use crate::graphql::mutation::mfa;
+use crate::graphql::mutation::user::throttle_subjects;
+use crate::graphql::objects::mfa::LoginResult;
+use crate::graphql::objects::user::Token;
+use crate::mail::Template;
+use crate::models::{
+    NewAuthAssignmentModel as NewAuthAssignment, NewUser, UserMfaModel as UserMfa,
+    UserModel as User, UserProfileModel as UserProfile,
+};
+use crate::utils::env::ENV;
+use crate::utils::revocation;
+use crate::utils::throttle::{Subject, Throttle};
+use crate::web_utils::jwt::{
+    create_login_link_token, decode_login_link_token, LOGIN_LINK_DURATION_IN_MINUTES,
+};
+use async_graphql::Context as GqlContext;
+use cuid::cuid;
+use diesel::prelude::*;
+use std::borrow::Cow;
+use validator::{validate_email, ValidationError, ValidationErrors};
+
+pub const LOGIN_LINK_THROTTLE: &str = "login_link";
+
+fn invalid_link() -> SrvError {
+    SrvError::Unauthorized(UnauthorizedInfo {
+        data: String::from("Invalid Login Link!"),
+    })
+}
+
+/// Sends a single use login link to the E-mail, always succeeds so it doesn't reveal if the E-mail
+/// exists. Every request is counted by the brute-force counters, so the E-mail can't be flooded
+///
+/// # Arguments
+/// * `ctx` - The GraphQL Context
+/// * `email` - The E-mail of the User, it doesn't need to have an account yet
+pub async fn request_login_link(ctx: &GqlContext<'_>, email: String) -> Result<bool, SrvError> {
+    let context = ctx.data::<Context>();
+    if !validate_email(&email) {
+        let mut error = ValidationError::new("email");
+        error.message = Some(Cow::from("This value should be a E-Mail"));
+        let mut errors = ValidationErrors::new();
+        errors.add("email", error);
+        return Err(SrvError::ValidationError(errors));
+    }
+    let throttle = Throttle::new(&context.redis_client, LOGIN_LINK_THROTTLE);
+    let subjects = throttle_subjects(context, &email);
+    throttle.check(&subjects)?;
+    throttle.register_failure(&subjects)?;
+    let locale = {
+        let conn: &MysqlConnection = &context.pool.get().unwrap();
+        match User::find_by_email_with_deleted(&email, conn)? {
+            Some(user) if user.deleted => return Ok(true),
+            Some(user) => UserProfile::locale_of(&user.id, conn)?,
+            None => None,
+        }
+    };
+    let token =
+        create_login_link_token(&email, &cuid().unwrap()).ok_or(SrvError::InternalServerError)?;
+    let link = format!("{}/login-link?token={}", ENV.app_url, token);
+    context
+        .mail_service
+        .send(
+            &email,
+            Template::LoginLink,
+            locale.as_deref(),
+            &[("link", link.as_str())],
+        )
+        .await?;
+    Ok(true)
+}
+
+/// Login a User with a login link, the User is created when the E-mail has no account yet. Returns a
+/// [`Token`] or a `MfaChallenge` when the User has Two-Factor Authentication enabled
+///
+/// # Arguments
+/// * `ctx` - The GraphQL Context
+/// * `token` - The token of the link received by E-mail
+pub fn login_with_link(ctx: &GqlContext<'_>, token: String) -> Result<LoginResult, SrvError> {
+    let context = ctx.data::<Context>();
+    let (email, token_id) = decode_login_link_token(&token).ok_or_else(invalid_link)?;
+    let ttl = (LOGIN_LINK_DURATION_IN_MINUTES * 60) as usize;
+    if !revocation::use_once(&context.redis_client, &token_id, ttl)? {
+        return Err(invalid_link());
+    }
+    let conn: &MysqlConnection = &context.pool.get().unwrap();
+    let user = conn.transaction::<_, SrvError, _>(|| {
+        let user = match User::find_by_email_with_deleted(&email, conn)? {
+            Some(user) if user.deleted => return Err(invalid_link()),
+            Some(user) => user,
+            None => {
+                let user = NewUser::new_without_pw(&email).save(conn)?;
+                NewAuthAssignment::new("user", &user.id).save(conn)?;
+                user
+            }
+        };
+        // Receiving the link proves that the E-mail belongs to the User
+        if user.email_verified_at.is_none() {
+            Ok(user.mark_email_verified(conn)?)
+        } else {
+            Ok(user)
+        }
+    })?;
+    Throttle::new(&context.redis_client, LOGIN_LINK_THROTTLE).reset(&[Subject::Account(&email)])?;
+    if UserMfa::find_enabled(&user.id, conn)?.is_some() {
+        return Ok(LoginResult::MfaChallenge(mfa::challenge(&user)?));
+    }
+    conn.transaction::<_, SrvError, _>(|| Token::from_user(user)?.save(&context.request_info, conn))
+        .map(LoginResult::Token)
+}
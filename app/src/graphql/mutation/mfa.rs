@@ -0,0 +1,197 @@
+use crate::errors::{DuplicateErrorInfo, SrvError, UnauthorizedInfo};
+use crate::graphql::context::Context;
+use crate::graphql::mutation::user::throttle_subjects;
+use crate::graphql::objects::mfa::{MfaChallenge, MfaEnrollment};
+use crate::graphql::objects::user::Token;
+use crate::graphql::utils::authorization::assert_user;
+use crate::models::{
+    NewUserMfaModel as NewUserMfa, UserMfaModel as UserMfa,
+    UserMfaRecoveryCodeModel as UserMfaRecoveryCode, UserModel as User,
+};
+use crate::utils::throttle::{Subject, Throttle};
+use crate::utils::token::{hash_token, random_token};
+use crate::utils::{encryption, env::ENV, totp};
+use crate::web_utils::jwt::{
+    create_mfa_challenge_token, decode_mfa_challenge_token, MFA_CHALLENGE_DURATION_IN_MINUTES,
+};
+use async_graphql::Context as GqlContext;
+use chrono::{Duration, Utc};
+use diesel::prelude::*;
+
+const RECOVERY_CODES_COUNT: usize = 10;
+pub const MFA_THROTTLE: &str = "mfa";
+
+/// Starts the Two-Factor Authentication enrollment of the authenticated User, returns the TOTP secret
+///
+/// # Arguments
+/// * `ctx` - The GraphQL Context
+pub fn enroll_mfa(ctx: &GqlContext<'_>) -> Result<MfaEnrollment, SrvError> {
+    let context = ctx.data::<Context>();
+    let user = assert_user(context.user())?;
+    let conn: &MysqlConnection = &context.pool.get().unwrap();
+    if UserMfa::find_enabled(&user.id, conn)?.is_some() {
+        return Err(already_enabled());
+    }
+    let secret = totp::generate_secret();
+    NewUserMfa::new(&user.id, encryption::encrypt(&secret)?).save(conn)?;
+    let secret = totp::encode_secret(&secret);
+    Ok(MfaEnrollment {
+        otpauth_uri: totp::otpauth_uri(&secret, &user.email, &ENV.mfa_issuer),
+        secret,
+    })
+}
+
+/// Confirms the enrollment with a code from the authenticator app, returns the recovery codes
+///
+/// # Arguments
+/// * `ctx` - The GraphQL Context
+/// * `code` - The current TOTP code
+pub fn confirm_mfa(ctx: &GqlContext<'_>, code: String) -> Result<Vec<String>, SrvError> {
+    let context = ctx.data::<Context>();
+    let user = assert_user(context.user())?;
+    let conn: &MysqlConnection = &context.pool.get().unwrap();
+    conn.transaction::<_, SrvError, _>(|| {
+        let mfa = UserMfa::find(&user.id, conn)?.ok_or(SrvError::NotFound)?;
+        if mfa.enabled_at.is_some() {
+            return Err(already_enabled());
+        }
+        let step = verify_totp(&mfa, &code)?.ok_or_else(invalid_code)?;
+        mfa.enable(step, conn)?;
+        generate_recovery_codes(user, conn)
+    })
+}
+
+/// Disables the Two-Factor Authentication of the authenticated User
+///
+/// # Arguments
+/// * `ctx` - The GraphQL Context
+/// * `code` - A TOTP code or a recovery code
+pub fn disable_mfa(ctx: &GqlContext<'_>, code: String) -> Result<bool, SrvError> {
+    let context = ctx.data::<Context>();
+    let user = assert_user(context.user())?;
+    let conn: &MysqlConnection = &context.pool.get().unwrap();
+    conn.transaction::<_, SrvError, _>(|| {
+        let mfa = UserMfa::find_enabled(&user.id, conn)?.ok_or(SrvError::NotFound)?;
+        verify_code(&mfa, &code, conn)?;
+        Ok(mfa.delete(conn)? > 0)
+    })
+}
+
+/// Replaces the recovery codes of the authenticated User, the previous codes stop working
+///
+/// # Arguments
+/// * `ctx` - The GraphQL Context
+/// * `code` - A TOTP code or a recovery code
+pub fn regenerate_mfa_recovery_codes(
+    ctx: &GqlContext<'_>,
+    code: String,
+) -> Result<Vec<String>, SrvError> {
+    let context = ctx.data::<Context>();
+    let user = assert_user(context.user())?;
+    let conn: &MysqlConnection = &context.pool.get().unwrap();
+    conn.transaction::<_, SrvError, _>(|| {
+        let mfa = UserMfa::find_enabled(&user.id, conn)?.ok_or(SrvError::NotFound)?;
+        verify_code(&mfa, &code, conn)?;
+        generate_recovery_codes(user, conn)
+    })
+}
+
+/// Finishes a login that returned a [`MfaChallenge`], returns the [`Token`]
+///
+/// # Arguments
+/// * `ctx` - The GraphQL Context
+/// * `challenge` - The challenge returned by the login
+/// * `code` - A TOTP code or a recovery code
+pub fn verify_mfa(
+    ctx: &GqlContext<'_>,
+    challenge: String,
+    code: String,
+) -> Result<Token, SrvError> {
+    use crate::schema::users::dsl::{deleted, id, users};
+    let context = ctx.data::<Context>();
+    let user_id = decode_mfa_challenge_token(&challenge).ok_or_else(|| {
+        SrvError::Unauthorized(UnauthorizedInfo {
+            data: String::from("Invalid Two-Factor Authentication Challenge!"),
+        })
+    })?;
+    let conn: &MysqlConnection = &context.pool.get().unwrap();
+    let user = users
+        .filter(id.eq(&user_id))
+        .filter(deleted.eq(false))
+        .first::<User>(conn)?;
+    let throttle = Throttle::new(&context.redis_client, MFA_THROTTLE);
+    let subjects = throttle_subjects(context, &user.email);
+    throttle.check(&subjects)?;
+    let result = conn.transaction::<_, SrvError, _>(|| {
+        let mfa = UserMfa::find_enabled(&user.id, conn)?.ok_or_else(invalid_code)?;
+        verify_code(&mfa, &code, conn)
+    });
+    if let Err(error) = result {
+        throttle.register_failure(&subjects)?;
+        return Err(error);
+    }
+    throttle.reset(&[Subject::Account(&user.email)])?;
+    Ok(Token::from_user(user)?.save(&context.request_info, conn)?)
+}
+
+/// Creates the challenge returned by the login of a User with Two-Factor Authentication
+pub fn challenge(user: &User) -> Result<MfaChallenge, SrvError> {
+    Ok(MfaChallenge {
+        challenge: create_mfa_challenge_token(&user.id).ok_or(SrvError::InternalServerError)?,
+        expires_at: Utc::now() + Duration::minutes(MFA_CHALLENGE_DURATION_IN_MINUTES),
+    })
+}
+
+fn verify_totp(mfa: &UserMfa, code: &str) -> Result<Option<i64>, SrvError> {
+    let secret = encryption::decrypt(&mfa.secret_encrypted)?;
+    Ok(
+        totp::verify(&secret, code, Utc::now().timestamp()).filter(|step| {
+            mfa.last_used_step
+                .map_or(true, |last_step| *step > last_step)
+        }),
+    )
+}
+
+/// Accepts a TOTP code that was not used yet or an unused recovery code
+fn verify_code(mfa: &UserMfa, code: &str, conn: &MysqlConnection) -> Result<(), SrvError> {
+    if let Some(step) = verify_totp(mfa, code)? {
+        mfa.use_step(step, conn)?;
+        return Ok(());
+    }
+    if UserMfaRecoveryCode::use_code(&mfa.user_id, &hash_recovery_code(code), conn)? {
+        return Ok(());
+    }
+    Err(invalid_code())
+}
+
+fn generate_recovery_codes(user: &User, conn: &MysqlConnection) -> Result<Vec<String>, SrvError> {
+    let codes: Vec<String> = (0..RECOVERY_CODES_COUNT)
+        .map(|_| {
+            let token = random_token();
+            format!("{}-{}", &token[0..5], &token[5..10])
+        })
+        .collect();
+    UserMfaRecoveryCode::replace_from_user(
+        &user.id,
+        codes.iter().map(|code| hash_recovery_code(code)).collect(),
+        conn,
+    )?;
+    Ok(codes)
+}
+
+fn hash_recovery_code(code: &str) -> String {
+    hash_token(&code.trim().replace('-', "").to_lowercase())
+}
+
+fn invalid_code() -> SrvError {
+    SrvError::Unauthorized(UnauthorizedInfo {
+        data: String::from("Invalid Two-Factor Authentication Code!"),
+    })
+}
+
+fn already_enabled() -> SrvError {
+    SrvError::Duplicate(DuplicateErrorInfo {
+        origin: String::from("mfa"),
+        info: String::from("Two-Factor Authentication is already enabled!"),
+    })
+}
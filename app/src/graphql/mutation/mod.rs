@@ -1,8 +1,22 @@
 use crate::graphql::guards::*;
 use crate::graphql::input::*;
-use crate::graphql::objects::user::Token;
-use async_graphql::{guard::Guard, Context, FieldError};
+use crate::graphql::objects::auth_item::AuthItem;
+use crate::graphql::objects::data_export::DataExport;
+use crate::graphql::objects::external_user_provider::ExternalUserProvider;
+use crate::graphql::objects::impersonation::Impersonation;
+use crate::graphql::objects::mfa::{LoginResult, MfaEnrollment};
+use crate::graphql::objects::oauth_client::{OAuthClient, OAuthClientCredentials};
+use crate::graphql::objects::personal_access_token::PersonalAccessTokenCredentials;
+use crate::graphql::objects::user::{Token, User};
+use async_graphql::{guard::Guard, Context, FieldError, Upload, ID};
 
+pub mod auth_item;
+pub mod data_export;
+pub mod impersonation;
+pub mod login_link;
+pub mod mfa;
+pub mod oauth_client;
+pub mod personal_access_token;
 pub mod user;
 pub struct Mutation;
 
@@ -10,33 +24,331 @@ type AuthResult = Result<Token, FieldError>;
 
 #[async_graphql::Object]
 impl Mutation {
-    pub async fn login(&self, ctx: &Context<'_>, input: UserLoginInput) -> AuthResult {
+    #[field(guard(ImpersonationGuard()))]
+    pub async fn login(
+        &self,
+        ctx: &Context<'_>,
+        input: UserLoginInput,
+    ) -> Result<LoginResult, FieldError> {
         Ok(user::login(ctx, input)?)
     }
 
+    #[field(guard(ImpersonationGuard()))]
     pub async fn login_with_external_user(
         &self,
         ctx: &Context<'_>,
         input: UserExternalDataInput,
-    ) -> AuthResult {
-        Ok(user::login_with_external_user(ctx, input)?)
+    ) -> Result<LoginResult, FieldError> {
+        Ok(user::login_with_external_user(ctx, input).await?)
     }
 
-    pub async fn register(&self, ctx: &Context<'_>, user: UserRegisterInput) -> AuthResult {
-        Ok(user::register(ctx, user)?)
+    #[field(guard(ImpersonationGuard()))]
+    pub async fn register(
+        &self,
+        ctx: &Context<'_>,
+        user: UserRegisterInput,
+    ) -> Result<Option<Token>, FieldError> {
+        Ok(user::register(ctx, user).await?)
     }
 
+    #[field(guard(ImpersonationGuard()))]
     pub async fn refresh_token(&self, ctx: &Context<'_>, refresh_token: String) -> AuthResult {
         Ok(user::refresh_token(ctx, refresh_token)?)
     }
 
-    #[field(guard(AuthGuard()))]
+    #[field(guard(AuthGuard(), ImpersonationGuard()))]
     pub async fn logout(&self, ctx: &Context<'_>) -> Result<bool, FieldError> {
         Ok(user::logout(ctx)?)
     }
 
-    #[field(guard(AuthGuard()))]
+    #[field(guard(AccountGuard()))]
     pub async fn update_user(&self, ctx: &Context<'_>, input: UserUpdateInput) -> AuthResult {
-        Ok(user::update_user(ctx, input)?)
+        Ok(user::update_user(ctx, input).await?)
+    }
+
+    #[field(guard(
+        AccessGuard(role = "Role::User", scope = "Scope::ProfileWrite"),
+        ImpersonationGuard()
+    ))]
+    pub async fn update_profile(
+        &self,
+        ctx: &Context<'_>,
+        input: UserProfileInput,
+    ) -> Result<User, FieldError> {
+        Ok(user::update_profile(ctx, input)?)
+    }
+
+    #[field(guard(
+        AccessGuard(role = "Role::User", scope = "Scope::ProfileWrite"),
+        ImpersonationGuard()
+    ))]
+    pub async fn upload_avatar(&self, ctx: &Context<'_>, file: Upload) -> Result<User, FieldError> {
+        Ok(user::upload_avatar(ctx, file).await?)
+    }
+
+    #[field(guard(AccountGuard()))]
+    pub async fn link_external_provider(
+        &self,
+        ctx: &Context<'_>,
+        input: UserExternalDataInput,
+    ) -> Result<ExternalUserProvider, FieldError> {
+        Ok(user::link_external_provider(ctx, input).await?)
+    }
+
+    #[field(guard(AccountGuard()))]
+    pub async fn unlink_external_provider(
+        &self,
+        ctx: &Context<'_>,
+        id: ID,
+    ) -> Result<bool, FieldError> {
+        Ok(user::unlink_external_provider(ctx, id)?)
+    }
+
+    #[field(guard(ImpersonationGuard()))]
+    pub async fn request_login_link(
+        &self,
+        ctx: &Context<'_>,
+        email: String,
+    ) -> Result<bool, FieldError> {
+        Ok(login_link::request_login_link(ctx, email).await?)
+    }
+
+    #[field(guard(ImpersonationGuard()))]
+    pub async fn login_with_link(
+        &self,
+        ctx: &Context<'_>,
+        token: String,
+    ) -> Result<LoginResult, FieldError> {
+        Ok(login_link::login_with_link(ctx, token)?)
+    }
+
+    #[field(guard(ImpersonationGuard()))]
+    pub async fn request_password_reset(
+        &self,
+        ctx: &Context<'_>,
+        email: String,
+    ) -> Result<bool, FieldError> {
+        Ok(user::request_password_reset(ctx, email)?)
+    }
+
+    #[field(guard(ImpersonationGuard()))]
+    pub async fn reset_password(
+        &self,
+        ctx: &Context<'_>,
+        input: PasswordResetInput,
+    ) -> Result<bool, FieldError> {
+        Ok(user::reset_password(ctx, input)?)
+    }
+
+    #[field(guard(ImpersonationGuard()))]
+    pub async fn verify_email(&self, ctx: &Context<'_>, token: String) -> Result<bool, FieldError> {
+        Ok(user::verify_email(ctx, token)?)
+    }
+
+    #[field(guard(AuthGuard(), ImpersonationGuard()))]
+    pub async fn resend_verification_email(&self, ctx: &Context<'_>) -> Result<bool, FieldError> {
+        Ok(user::resend_verification_email(ctx).await?)
+    }
+
+    #[field(guard(ImpersonationGuard()))]
+    pub async fn verify_mfa(
+        &self,
+        ctx: &Context<'_>,
+        challenge: String,
+        code: String,
+    ) -> AuthResult {
+        Ok(mfa::verify_mfa(ctx, challenge, code)?)
+    }
+
+    #[field(guard(AccountGuard()))]
+    pub async fn enroll_mfa(&self, ctx: &Context<'_>) -> Result<MfaEnrollment, FieldError> {
+        Ok(mfa::enroll_mfa(ctx)?)
+    }
+
+    #[field(guard(AccountGuard()))]
+    pub async fn confirm_mfa(
+        &self,
+        ctx: &Context<'_>,
+        code: String,
+    ) -> Result<Vec<String>, FieldError> {
+        Ok(mfa::confirm_mfa(ctx, code)?)
+    }
+
+    #[field(guard(AccountGuard()))]
+    pub async fn disable_mfa(&self, ctx: &Context<'_>, code: String) -> Result<bool, FieldError> {
+        Ok(mfa::disable_mfa(ctx, code)?)
+    }
+
+    #[field(guard(AccountGuard()))]
+    pub async fn regenerate_mfa_recovery_codes(
+        &self,
+        ctx: &Context<'_>,
+        code: String,
+    ) -> Result<Vec<String>, FieldError> {
+        Ok(mfa::regenerate_mfa_recovery_codes(ctx, code)?)
+    }
+
+    #[field(guard(AccountGuard()))]
+    pub async fn revoke_session(&self, ctx: &Context<'_>, id: ID) -> Result<bool, FieldError> {
+        Ok(user::revoke_session(ctx, id)?)
+    }
+
+    #[field(guard(AccountGuard()))]
+    pub async fn revoke_other_sessions(&self, ctx: &Context<'_>) -> Result<i32, FieldError> {
+        Ok(user::revoke_other_sessions(ctx)?)
+    }
+
+    #[field(guard(
+        AccessGuard(role = "Role::Admin", scope = "Scope::UsersWrite"),
+        ImpersonationGuard()
+    ))]
+    pub async fn unlock_account(
+        &self,
+        ctx: &Context<'_>,
+        email: String,
+    ) -> Result<bool, FieldError> {
+        Ok(user::unlock_account(ctx, email)?)
+    }
+
+    #[field(guard(AccountGuard()))]
+    pub async fn delete_my_account(
+        &self,
+        ctx: &Context<'_>,
+        password: Option<String>,
+    ) -> Result<bool, FieldError> {
+        Ok(user::delete_my_account(ctx, password)?)
+    }
+
+    #[field(guard(
+        AccessGuard(role = "Role::Admin", scope = "Scope::UsersWrite"),
+        ImpersonationGuard()
+    ))]
+    pub async fn delete_user(&self, ctx: &Context<'_>, id: ID) -> Result<bool, FieldError> {
+        Ok(user::delete_user(ctx, id)?)
+    }
+
+    #[field(guard(
+        AccessGuard(role = "Role::Admin", scope = "Scope::UsersWrite"),
+        ImpersonationGuard()
+    ))]
+    pub async fn restore_user(&self, ctx: &Context<'_>, id: ID) -> Result<User, FieldError> {
+        Ok(user::restore_user(ctx, id)?)
+    }
+
+    #[field(guard(AccountGuard()))]
+    pub async fn request_data_export(&self, ctx: &Context<'_>) -> Result<DataExport, FieldError> {
+        Ok(data_export::request_data_export(ctx)?)
+    }
+
+    #[field(guard(RoleGuard(role = "Role::Admin"), ImpersonationGuard()))]
+    pub async fn request_user_data_export(
+        &self,
+        ctx: &Context<'_>,
+        user_id: ID,
+    ) -> Result<DataExport, FieldError> {
+        Ok(data_export::request_user_data_export(ctx, user_id)?)
+    }
+
+    #[field(guard(RoleGuard(role = "Role::Admin"), ImpersonationGuard()))]
+    pub async fn create_oauth_client(
+        &self,
+        ctx: &Context<'_>,
+        input: OAuthClientInput,
+    ) -> Result<OAuthClientCredentials, FieldError> {
+        Ok(oauth_client::create_oauth_client(ctx, input)?)
+    }
+
+    #[field(guard(RoleGuard(role = "Role::Admin"), ImpersonationGuard()))]
+    pub async fn revoke_oauth_client(
+        &self,
+        ctx: &Context<'_>,
+        client_id: ID,
+    ) -> Result<OAuthClient, FieldError> {
+        Ok(oauth_client::revoke_oauth_client(ctx, client_id)?)
+    }
+
+    #[field(guard(AccountGuard()))]
+    pub async fn create_personal_access_token(
+        &self,
+        ctx: &Context<'_>,
+        input: PersonalAccessTokenInput,
+    ) -> Result<PersonalAccessTokenCredentials, FieldError> {
+        Ok(personal_access_token::create_personal_access_token(
+            ctx, input,
+        )?)
+    }
+
+    #[field(guard(AccountGuard()))]
+    pub async fn revoke_personal_access_token(
+        &self,
+        ctx: &Context<'_>,
+        id: ID,
+    ) -> Result<bool, FieldError> {
+        Ok(personal_access_token::revoke_personal_access_token(
+            ctx, id,
+        )?)
+    }
+
+    #[field(guard(RoleGuard(role = "Role::Admin"), ImpersonationGuard()))]
+    pub async fn impersonate_user(
+        &self,
+        ctx: &Context<'_>,
+        id: ID,
+        reason: String,
+    ) -> Result<Impersonation, FieldError> {
+        Ok(impersonation::impersonate_user(ctx, id, reason)?)
+    }
+
+    #[field(guard(AuthGuard()))]
+    pub async fn end_impersonation(&self, ctx: &Context<'_>) -> Result<bool, FieldError> {
+        Ok(impersonation::end_impersonation(ctx)?)
+    }
+
+    #[field(guard(RoleGuard(role = "Role::Admin"), ImpersonationGuard()))]
+    pub async fn create_auth_item(
+        &self,
+        ctx: &Context<'_>,
+        input: AuthItemInput,
+    ) -> Result<AuthItem, FieldError> {
+        Ok(auth_item::create_auth_item(ctx, input)?)
+    }
+
+    #[field(guard(RoleGuard(role = "Role::Admin"), ImpersonationGuard()))]
+    pub async fn update_auth_item(
+        &self,
+        ctx: &Context<'_>,
+        name: String,
+        input: AuthItemUpdateInput,
+    ) -> Result<AuthItem, FieldError> {
+        Ok(auth_item::update_auth_item(ctx, name, input)?)
+    }
+
+    #[field(guard(RoleGuard(role = "Role::Admin"), ImpersonationGuard()))]
+    pub async fn delete_auth_item(
+        &self,
+        ctx: &Context<'_>,
+        name: String,
+    ) -> Result<bool, FieldError> {
+        Ok(auth_item::delete_auth_item(ctx, name)?)
+    }
+
+    #[field(guard(RoleGuard(role = "Role::Admin"), ImpersonationGuard()))]
+    pub async fn add_auth_item_child(
+        &self,
+        ctx: &Context<'_>,
+        parent: String,
+        child: String,
+    ) -> Result<AuthItem, FieldError> {
+        Ok(auth_item::add_auth_item_child(ctx, parent, child)?)
+    }
+
+    #[field(guard(RoleGuard(role = "Role::Admin"), ImpersonationGuard()))]
+    pub async fn remove_auth_item_child(
+        &self,
+        ctx: &Context<'_>,
+        parent: String,
+        child: String,
+    ) -> Result<bool, FieldError> {
+        Ok(auth_item::remove_auth_item_child(ctx, parent, child)?)
     }
 }
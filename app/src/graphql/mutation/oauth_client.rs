@@ -0,0 +1,76 @@
+use crate::errors::SrvError;
+use crate::graphql::context::Context;
+use crate::graphql::input::OAuthClientInput;
+use crate::graphql::objects::oauth_client::{OAuthClient, OAuthClientCredentials};
+use crate::graphql::utils::audit;
+use crate::models::{AuditEvent, NewOAuthClientModel as NewOAuthClient};
+use crate::utils::revocation;
+use crate::utils::token::random_token;
+use async_graphql::{Context as GqlContext, ID};
+use diesel::prelude::*;
+use validator::Validate;
+
+/// Registers a service as an OAuth2 client, its secret is only returned here
+///
+/// # Arguments
+/// * `ctx` - The GraphQL Context
+/// * `input` - The name and the allowed scopes of the client
+pub fn create_oauth_client(
+    ctx: &GqlContext<'_>,
+    input: OAuthClientInput,
+) -> Result<OAuthClientCredentials, SrvError> {
+    input.validate()?;
+    let context = ctx.data::<Context>();
+    let conn: &MysqlConnection = &context.pool.get().unwrap();
+    let client_secret = random_token();
+    let client = conn.transaction::<_, SrvError, _>(|| {
+        let client = NewOAuthClient::new(
+            &input.name,
+            &client_secret,
+            &input.scopes(),
+            context.user_id().map(|id| id.as_str()),
+        )
+        .save(conn)?;
+        let details = Some(format!(
+            "client: {}, scopes: {}, by {}",
+            client.id,
+            client.scopes,
+            context.actor().unwrap_or_default()
+        ));
+        audit::record(context, AuditEvent::OAuthClientCreated, None, details, conn)?;
+        Ok(client)
+    })?;
+    Ok(OAuthClientCredentials {
+        client,
+        client_secret,
+    })
+}
+
+/// Revokes an OAuth2 client, the tokens already issued for it stop working too
+///
+/// # Arguments
+/// * `ctx` - The GraphQL Context
+/// * `client_id` - The id of the client
+pub fn revoke_oauth_client(ctx: &GqlContext<'_>, client_id: ID) -> Result<OAuthClient, SrvError> {
+    let context = ctx.data::<Context>();
+    let conn: &MysqlConnection = &context.pool.get().unwrap();
+    let client = conn.transaction::<_, SrvError, _>(|| {
+        let client = OAuthClient::find_active(&client_id, conn)?.ok_or(SrvError::NotFound)?;
+        client.revoke(conn)?;
+        audit::record(
+            context,
+            AuditEvent::OAuthClientRevoked,
+            None,
+            Some(format!(
+                "client: {}, by {}",
+                client.id,
+                context.actor().unwrap_or_default()
+            )),
+            conn,
+        )?;
+        Ok(client)
+    })?;
+    // The client tokens are checked against the same list as the sessions and never outlive it
+    revocation::revoke_sessions(&context.redis_client, &[client.id.clone()])?;
+    Ok(OAuthClient::find(&client.id, conn)?.ok_or(SrvError::NotFound)?)
+}
@@ -0,0 +1,81 @@
+use crate::errors::SrvError;
+use crate::graphql::context::Context;
+use crate::graphql::input::PersonalAccessTokenInput;
+use crate::graphql::objects::personal_access_token::{
+    PersonalAccessToken, PersonalAccessTokenCredentials,
+};
+use crate::graphql::utils::audit;
+use crate::graphql::utils::authorization::assert_user;
+use crate::models::{
+    AuditEvent, NewPersonalAccessTokenModel as NewPersonalAccessToken, PERSONAL_ACCESS_TOKEN_PREFIX,
+};
+use crate::utils::token::random_token;
+use async_graphql::{Context as GqlContext, ID};
+use diesel::prelude::*;
+use validator::Validate;
+
+/// Creates a personal access token of the authenticated User, its secret is only returned here
+///
+/// # Arguments
+/// * `ctx` - The GraphQL Context
+/// * `input` - The name, the scopes and the optional expiration of the token
+pub fn create_personal_access_token(
+    ctx: &GqlContext<'_>,
+    input: PersonalAccessTokenInput,
+) -> Result<PersonalAccessTokenCredentials, SrvError> {
+    input.validate()?;
+    let context = ctx.data::<Context>();
+    let user = assert_user(context.user())?;
+    let conn: &MysqlConnection = &context.pool.get().unwrap();
+    let token = format!("{}{}", PERSONAL_ACCESS_TOKEN_PREFIX, random_token());
+    let personal_access_token = conn.transaction::<_, SrvError, _>(|| {
+        let personal_access_token = NewPersonalAccessToken::new(
+            &user.id,
+            &input.name,
+            &token,
+            &input.scopes(),
+            input.expires_at.map(|date| date.naive_utc()),
+        )
+        .save(conn)?;
+        let details = Some(format!(
+            "token: {}, scopes: {}",
+            personal_access_token.id, personal_access_token.scopes
+        ));
+        audit::record(
+            context,
+            AuditEvent::PersonalAccessTokenCreated,
+            Some(&user.id),
+            details,
+            conn,
+        )?;
+        Ok(personal_access_token)
+    })?;
+    Ok(PersonalAccessTokenCredentials {
+        personal_access_token,
+        token,
+    })
+}
+
+/// Revokes a personal access token of the authenticated User
+///
+/// # Arguments
+/// * `ctx` - The GraphQL Context
+/// * `id` - The id of the token
+pub fn revoke_personal_access_token(ctx: &GqlContext<'_>, id: ID) -> Result<bool, SrvError> {
+    let context = ctx.data::<Context>();
+    let user = assert_user(context.user())?;
+    let conn: &MysqlConnection = &context.pool.get().unwrap();
+    conn.transaction::<_, SrvError, _>(|| {
+        let personal_access_token =
+            PersonalAccessToken::find_for_user(&id, &user.id, conn)?.ok_or(SrvError::NotFound)?;
+        personal_access_token.revoke(conn)?;
+        audit::record(
+            context,
+            AuditEvent::PersonalAccessTokenRevoked,
+            Some(&user.id),
+            Some(format!("token: {}", personal_access_token.id)),
+            conn,
+        )?;
+        Ok(true)
+    })
+}
@@ -1,93 +1,333 @@
 use crate::graphql::context::Context;
 use crate::graphql::input::user::*;
+use crate::graphql::mutation::{login_link, mfa};
 use crate::graphql::objects::external_user_provider::UserProvider;
+use crate::graphql::objects::mfa::LoginResult;
+use crate::graphql::utils::audit;
 use crate::graphql::utils::authorization::assert_user;
+use crate::mail::Template;
 use crate::models::{
-    NewAuthAssignmentModel as NewAuthAssignment, NewUser, UpdatedUserModel as UpdatedUser,
-    UserModel as User, UserTokenModel as UserToken,
+    AuditEvent, ExternalUserProviderModel as ExternalUserProvider,
+    NewAuthAssignmentModel as NewAuthAssignment,
+    NewExternalUserProviderModel as NewExternalUserProvider,
+    NewPasswordResetTokenModel as NewPasswordResetToken,
+    NewUsedRefreshTokenModel as NewUsedRefreshToken, NewUser,
+    PasswordResetTokenModel as PasswordResetToken, UpdatedUserModel as UpdatedUser,
+    UpdatedUserProfileModel as UpdatedUserProfile, UsedRefreshTokenModel as UsedRefreshToken,
+    UserMfaModel as UserMfa, UserModel as User, UserProfileModel as UserProfile,
+    UserTokenModel as UserToken,
 };
-use crate::utils::argon::make_hash;
+use crate::utils::argon::verify_dummy_password;
+use crate::utils::avatar;
+use crate::utils::env::{EmailVerificationPolicy, ENV};
+use crate::utils::password_policy::validate_with_password_policy;
+use crate::utils::revocation;
+use crate::utils::throttle::{Subject, Throttle};
+use crate::utils::token::{hash_token, random_token};
+use crate::web_utils::jwt::{create_email_verification_token, decode_email_verification_token};
+use crate::web_utils::providers::{self, apple, facebook, google, ExternalUserInfo};
 use crate::{
-    errors::{SrvError, UnauthorizedInfo},
+    errors::{DuplicateErrorInfo, SrvError, UnauthorizedInfo},
     graphql::objects::user::Token,
 };
-use async_graphql::{Context as GqlContext, FieldError};
+use actix_web::web;
+use async_graphql::{Context as GqlContext, FieldError, Upload, ID};
 use chrono::Utc;
 use diesel::prelude::*;
 use validator::Validate;
 
 pub type AuthResult = Result<Token, SrvError>;
 
-/// Registers a User using a Local Authentication Process, returns a [`AuthResult`]
+pub const LOGIN_THROTTLE: &str = "login";
+pub const PASSWORD_RESET_THROTTLE: &str = "password_reset";
+
+/// Registers a User using a Local Authentication Process, returns its [`Token`] or `None` when the
+/// E-mail must be verified before logging in
 ///
 /// # Arguments
 ///
 /// * `ctx` - The GraphQL Context
 /// * `input` - The User data Input
-pub fn register(ctx: &GqlContext<'_>, input: UserRegisterInput) -> AuthResult {
+pub async fn register(
+    ctx: &GqlContext<'_>,
+    input: UserRegisterInput,
+) -> Result<Option<Token>, SrvError> {
     let context = ctx.data::<Context>();
-    input.validate()?;
-    let conn: &MysqlConnection = &context.pool.get().unwrap();
+    validate_with_password_policy(&input, "password", Some(&input.password), &input.email)?;
     let UserRegisterInput {
-        email, password, ..
+        email,
+        name,
+        password,
     } = input;
-    let user = NewUser::new(&email, &password).save(conn)?;
-    NewAuthAssignment::new("user", &user.id).save(conn)?;
-    Ok(Token::from_user(user)?.save(conn)?)
+    let user = {
+        let conn: &MysqlConnection = &context.pool.get().unwrap();
+        conn.transaction::<_, SrvError, _>(|| {
+            let user = NewUser::new(&email, &password).save(conn)?;
+            NewAuthAssignment::new("user", &user.id).save(conn)?;
+            UpdatedUserProfile {
+                display_name: Some(Some(name)),
+                ..Default::default()
+            }
+            .save(&user.id, conn)?;
+            Ok(user)
+        })?
+    };
+    // The account already exists, a failed delivery only means that the link has to be sent again
+    if let Err(error) = send_verification_email(context, &user).await {
+        println!("{:?}", error);
+    }
+    if assert_email_verification_policy(&user).is_err() {
+        return Ok(None);
+    }
+    let conn: &MysqlConnection = &context.pool.get().unwrap();
+    Ok(Some(
+        Token::from_user(user)?.save(&context.request_info, conn)?,
+    ))
 }
 
-/// Login a User using a Local Authentication Process, returns a [`AuthResult`]
+/// Login a User using a Local Authentication Process, returns a [`Token`] or a `MfaChallenge`
+/// when the User has Two-Factor Authentication enabled
 ///
 /// # Arguments
 ///
 /// * `ctx` - The GraphQL Context
 /// * `input` - The User Data Input
-pub fn login(ctx: &GqlContext<'_>, input: UserLoginInput) -> AuthResult {
+pub fn login(ctx: &GqlContext<'_>, input: UserLoginInput) -> Result<LoginResult, SrvError> {
     use crate::schema::users::dsl::*;
     let context = ctx.data::<Context>();
     input.validate()?;
+    let throttle = Throttle::new(&context.redis_client, LOGIN_THROTTLE);
+    let subjects = throttle_subjects(context, &input.email);
+    throttle.check(&subjects)?;
     let conn: &MysqlConnection = &context.pool.get().unwrap();
-    if let Ok(user) = users.filter(email.eq(input.email)).first::<User>(conn) {
-        return if make_hash(&input.password, &user.salt) == user.hash {
-            conn.transaction::<_, SrvError, _>(|| Token::from_user(user)?.save(conn))
-        } else {
-            Err(SrvError::Unauthorized(UnauthorizedInfo {
-                data: String::from("Wrong Password!"),
-            }))
-        };
+    let user = match users
+        .filter(email.eq(&input.email))
+        .filter(deleted.eq(false))
+        .first::<User>(conn)
+    {
+        Ok(user) if user.check_password(&input.password) => user,
+        // The same error for a wrong E-mail or password, so accounts can't be enumerated
+        result => {
+            if result.is_err() {
+                verify_dummy_password(&input.password);
+            }
+            throttle.register_failure(&subjects)?;
+            return Err(SrvError::Unauthorized(UnauthorizedInfo {
+                data: String::from("Invalid Credentials!"),
+            }));
+        }
+    };
+    throttle.reset(&[Subject::Account(&input.email)])?;
+    // The plain password is only known here, so old hashes are upgraded on login
+    let user = if user.password_needs_rehash() {
+        user.rehash_password(&input.password, conn)?
     } else {
-        Err(SrvError::Unauthorized(UnauthorizedInfo {
-            data: String::from("Wrong Email!"),
-        }))
+        user
+    };
+    assert_email_verification_policy(&user)?;
+    if UserMfa::find_enabled(&user.id, conn)?.is_some() {
+        return Ok(LoginResult::MfaChallenge(mfa::challenge(&user)?));
     }
+    conn.transaction::<_, SrvError, _>(|| Token::from_user(user)?.save(&context.request_info, conn))
+        .map(LoginResult::Token)
 }
 
-/// Login a User using a External User Authentication Process, returns a [`AuthResult`]
+/// The account and the client IP, when it is known, whose login attempts are counted
+pub fn throttle_subjects<'a>(context: &'a Context, account: &'a str) -> Vec<Subject<'a>> {
+    let mut subjects = vec![Subject::Account(account)];
+    if let Some(ip) = &context.request_info.ip {
+        subjects.push(Subject::Ip(ip));
+    }
+    subjects
+}
+
+/// Login a User using a External User Authentication Process, returns a [`Token`] or a
+/// `MfaChallenge` when the User has Two-Factor Authentication enabled
 ///
 /// # Arguments
 /// * `ctx` - The GraphQL Context
 /// * `input` - The User Data Input
-pub fn login_with_external_user(ctx: &GqlContext<'_>, input: UserExternalDataInput) -> AuthResult {
-    use crate::schema::users::dsl::*;
+pub async fn login_with_external_user(
+    ctx: &GqlContext<'_>,
+    input: UserExternalDataInput,
+) -> Result<LoginResult, SrvError> {
     let context = ctx.data::<Context>();
     let UserExternalDataInput { token, provider } = input;
     match provider {
-        UserProvider::Facebook => login_with_facebook(context, token),
-        UserProvider::Google => login_with_google(context, token),
-        UserProvider::Apple => login_with_apple(context, token),
+        UserProvider::Facebook => login_with_facebook(context, token).await,
+        UserProvider::Google => login_with_google(context, token).await,
+        UserProvider::Apple => login_with_apple(context, token).await,
     }
 }
 
-pub fn login_with_facebook(context: &Context, token: String) -> AuthResult {
-    Err(SrvError::Unavailable)
+/// Login with a Facebook User Access Token, validated through the configured Graph API
+pub async fn login_with_facebook(
+    context: &Context,
+    token: String,
+) -> Result<LoginResult, SrvError> {
+    let user_info = facebook::verify_access_token(&token).await?;
+    login_with_external_info(context, user_info)
 }
 
-pub fn login_with_google(context: &Context, token: String) -> AuthResult {
-    Err(SrvError::Unavailable)
+/// Login with a Google ID Token, the account is linked by the verified E-mail when possible
+pub async fn login_with_google(context: &Context, token: String) -> Result<LoginResult, SrvError> {
+    let user_info = google::verify_id_token(&token).await?;
+    login_with_external_info(context, user_info)
+}
+
+/// Login with a Sign in with Apple identity token, the account is only linked by its `external_id`
+pub async fn login_with_apple(context: &Context, token: String) -> Result<LoginResult, SrvError> {
+    let user_info = apple::verify_identity_token(&token).await?;
+    login_with_external_info(context, user_info)
+}
+
+/// Finds or creates the User of a verified External Provider account and logs it in like [`login`]
+///
+/// An existing local account is only linked when the provider verified the E-mail and allows it
+fn login_with_external_info(
+    context: &Context,
+    user_info: ExternalUserInfo,
+) -> Result<LoginResult, SrvError> {
+    let conn: &MysqlConnection = &context.pool.get().unwrap();
+    let user = conn.transaction::<_, SrvError, _>(|| {
+        if let Some((external_user, user)) =
+            ExternalUserProvider::find_with_user(user_info.provider, &user_info.external_id, conn)?
+        {
+            // Providers such as Apple only send the E-mail on some sign ins
+            if let Some(email) = &user_info.email {
+                if external_user.email.as_ref() != Some(email) {
+                    external_user.update_email(email, conn)?;
+                }
+            }
+            fill_profile(&user.id, &user_info, conn)?;
+            return Ok(user);
+        }
+        let email = user_info.email.as_ref().ok_or_else(|| {
+            SrvError::Unauthorized(UnauthorizedInfo {
+                data: String::from("The External Provider did not share an E-mail!"),
+            })
+        })?;
+        // A deleted account keeps its E-mail, so it can still be restored
+        let user = match User::find_by_email_with_deleted(email, conn)? {
+            Some(user) if !user.deleted && user_info.link_by_email && user_info.email_verified => {
+                user
+            }
+            Some(_) => {
+                return Err(SrvError::Duplicate(DuplicateErrorInfo {
+                    origin: String::from("email"),
+                    info: String::from("This E-mail is already used by another account!"),
+                }))
+            }
+            None => {
+                let user = NewUser::new_without_pw(email).save(conn)?;
+                NewAuthAssignment::new("user", &user.id).save(conn)?;
+                user
+            }
+        };
+        let user = if user_info.email_verified && user.email_verified_at.is_none() {
+            user.mark_email_verified(conn)?
+        } else {
+            user
+        };
+        NewExternalUserProvider::new(
+            user.id.clone(),
+            user_info.external_id.clone(),
+            user_info.provider,
+            user_info.email.as_ref(),
+        )
+        .save(conn)?;
+        fill_profile(&user.id, &user_info, conn)?;
+        Ok(user)
+    })?;
+    assert_email_verification_policy(&user)?;
+    if UserMfa::find_enabled(&user.id, conn)?.is_some() {
+        return Ok(LoginResult::MfaChallenge(mfa::challenge(&user)?));
+    }
+    conn.transaction::<_, SrvError, _>(|| Token::from_user(user)?.save(&context.request_info, conn))
+        .map(LoginResult::Token)
+}
+
+/// Fills the profile fields that the User didn't set with the data shared by the External Provider
+fn fill_profile(
+    user_id: &str,
+    user_info: &ExternalUserInfo,
+    conn: &MysqlConnection,
+) -> Result<(), SrvError> {
+    let profile = UserProfile::find(user_id, conn)?;
+    user_info
+        .profile()
+        .missing_from(&profile)
+        .save(user_id, conn)?;
+    Ok(())
+}
+
+/// Links an External Provider account to the authenticated User
+///
+/// # Arguments
+/// * `ctx` - The GraphQL Context
+/// * `input` - The External Provider token
+pub async fn link_external_provider(
+    ctx: &GqlContext<'_>,
+    input: UserExternalDataInput,
+) -> Result<ExternalUserProvider, SrvError> {
+    let context = ctx.data::<Context>();
+    let user = assert_user(context.user())?;
+    let UserExternalDataInput { token, provider } = input;
+    let user_info = providers::verify_token(provider.into(), &token).await?;
+    let conn: &MysqlConnection = &context.pool.get().unwrap();
+    conn.transaction::<_, SrvError, _>(|| {
+        match ExternalUserProvider::find_with_user(
+            user_info.provider,
+            &user_info.external_id,
+            conn,
+        )? {
+            Some((external_user, linked_user)) if linked_user.id == user.id => Ok(external_user),
+            Some(_) => Err(SrvError::Duplicate(DuplicateErrorInfo {
+                origin: String::from("external_id"),
+                info: String::from("This account is already linked to another User!"),
+            })),
+            None => {
+                let external_user = NewExternalUserProvider::new(
+                    user.id.clone(),
+                    user_info.external_id.clone(),
+                    user_info.provider,
+                    user_info.email.as_ref(),
+                )
+                .save(conn)?;
+                fill_profile(&user.id, &user_info, conn)?;
+                Ok(external_user)
+            }
+        }
+    })
 }
 
-pub fn login_with_apple(context: &Context, token: String) -> AuthResult {
-    Err(SrvError::Unavailable)
+/// Unlinks an External Provider account from the authenticated User using a soft delete,
+/// the last login method of a User without password can't be removed
+///
+/// # Arguments
+/// * `ctx` - The GraphQL Context
+/// * `external_user_id` - The id of the External Provider link
+pub fn unlink_external_provider(
+    ctx: &GqlContext<'_>,
+    external_user_id: ID,
+) -> Result<bool, SrvError> {
+    use crate::schema::external_user_providers::dsl::*;
+    let context = ctx.data::<Context>();
+    let user = assert_user(context.user())?;
+    let conn: &MysqlConnection = &context.pool.get().unwrap();
+    conn.transaction::<_, SrvError, _>(|| {
+        let external_user = external_user_providers
+            .filter(id.eq(external_user_id.to_string()))
+            .filter(user_id.eq(&user.id))
+            .filter(deleted.eq(false))
+            .first::<ExternalUserProvider>(conn)?;
+        if !user.has_password() && ExternalUserProvider::count_by_user(user, conn)? <= 1 {
+            return Err(SrvError::Unauthorized(UnauthorizedInfo {
+                data: String::from("You can't remove your last login method!"),
+            }));
+        }
+        Ok(external_user.soft_delete(conn)? > 0)
+    })
 }
 
 /// Refresh the Authentication Token from a User, returns a [`AuthResult`]
@@ -97,41 +337,75 @@ pub fn login_with_apple(context: &Context, token: String) -> AuthResult {
 /// * `refresh_token` - The Refresh Token received from some other authentication method
 pub fn refresh_token(ctx: &GqlContext<'_>, refresh_token: String) -> AuthResult {
     use crate::schema::user_tokens::dsl::{
-        refresh_expire_at, refresh_token as r_token, user_tokens,
+        refresh_expire_at, refresh_token_hash as r_token_hash, user_tokens,
     };
-    use crate::schema::users::dsl::{id, users};
+    use crate::schema::users::dsl::{deleted, id, users};
     let context = ctx.data::<Context>();
     let conn: &MysqlConnection = &context.pool.get().unwrap();
-    conn.transaction::<_, SrvError, _>(|| {
-        let user_token_result = user_tokens
-            .filter(r_token.eq(refresh_token))
+    let refresh_token_hash = hash_token(&refresh_token);
+    let rotated = conn.transaction::<_, SrvError, _>(|| {
+        let user_token = user_tokens
+            .filter(r_token_hash.eq(&refresh_token_hash))
             .filter(refresh_expire_at.ge(Utc::now().naive_local()))
-            .first::<UserToken>(conn);
-        match user_token_result {
-            Ok(user_token) => {
-                diesel::delete(&user_token).execute(conn)?;
+            .first::<UserToken>(conn)
+            .optional()?;
+        match user_token {
+            Some(user_token) => {
                 let user = users
-                    .filter(id.eq(user_token.user_id))
+                    .filter(id.eq(&user_token.user_id))
+                    .filter(deleted.eq(false))
                     .first::<User>(conn)?;
-                Token::from_user(user)?.save(conn)
+                // The session keeps its row, only the tokens are replaced
+                let token = Token::for_session(user, user_token.id.clone())?;
+                NewUsedRefreshToken::new(
+                    refresh_token_hash.clone(),
+                    &user_token.family_id,
+                    &user_token.user_id,
+                )
+                .save(conn)?;
+                let (token_hash, new_refresh_token_hash) = token.hashes();
+                user_token.rotate(&token_hash, &new_refresh_token_hash, conn)?;
+                Ok(Some(token))
             }
-            Err(_) => Err(SrvError::Unauthorized(UnauthorizedInfo {
-                data: String::from("Invalid Refresh Token!"),
-            })),
+            None => Ok(None),
         }
-    })
+    })?;
+    if let Some(token) = rotated {
+        return Ok(token);
+    }
+    // A rotated token presented again was stolen, either the thief or the User holds the family now
+    if let Some(used_token) = UsedRefreshToken::find(&refresh_token_hash, conn)? {
+        let revoked = conn.transaction::<_, SrvError, _>(|| {
+            let revoked = UserToken::revoke_family(&used_token.family_id, conn)?;
+            audit::record(
+                context,
+                AuditEvent::RefreshTokenReuse,
+                Some(&used_token.user_id),
+                Some(format!(
+                    "family: {}, revoked sessions: {}",
+                    used_token.family_id,
+                    revoked.len()
+                )),
+                conn,
+            )?;
+            Ok(revoked)
+        })?;
+        revocation::revoke_sessions(&context.redis_client, &revoked)?;
+    }
+    Err(SrvError::Unauthorized(UnauthorizedInfo {
+        data: String::from("Invalid Refresh Token!"),
+    }))
 }
 
 /// Logout a User, this invalidates the Authentication Token used in the Context
 pub fn logout(ctx: &GqlContext<'_>) -> Result<bool, SrvError> {
-    use crate::schema::user_tokens::dsl::{token, user_tokens};
     let context = ctx.data::<Context>();
-    assert_user(&context.user)?;
+    let user = assert_user(context.user())?;
+    let session_id = context.session_id().unwrap();
     let conn: &MysqlConnection = &context.pool.get().unwrap();
-    conn.transaction::<_, SrvError, _>(|| {
-        let user_token = context.user_token.as_ref().unwrap();
-        Ok(diesel::delete(user_tokens.filter(token.eq(user_token))).execute(conn)? > 0)
-    })
+    let deleted = UserToken::revoke(&user.id, session_id, conn)?;
+    revocation::revoke_sessions(&context.redis_client, &[session_id.clone()])?;
+    Ok(deleted)
 }
 
 /// Updates a User information such as Email or Password
@@ -139,20 +413,389 @@ pub fn logout(ctx: &GqlContext<'_>) -> Result<bool, SrvError> {
 /// # Arguments
 /// * `ctx` - The GraphQL Context
 /// * `input` - The User Data that will be Updated
-pub fn update_user(ctx: &GqlContext<'_>, input: UserUpdateInput) -> AuthResult {
-    use crate::schema::user_tokens::dsl::*;
-    let context = ctx.data::<Context>();
-    let user = assert_user(&context.user)?;
-    return match input.validate() {
-        Ok(_) => {
-            let conn: &MysqlConnection = &context.pool.get().unwrap();
-            conn.transaction::<_, SrvError, _>(|| {
-                let updated_user =
-                    UpdatedUser::new(input.email, input.password).update(user, conn)?;
-                diesel::delete(user_tokens.filter(user_id.eq(&user.id))).execute(conn)?;
-                Token::from_user(updated_user)?.save(conn)
-            })
+pub async fn update_user(ctx: &GqlContext<'_>, input: UserUpdateInput) -> AuthResult {
+    let context = ctx.data::<Context>();
+    let user = assert_user(context.user())?;
+    validate_with_password_policy(
+        &input,
+        "password",
+        input.password.as_deref(),
+        input.email.as_ref().unwrap_or(&user.email),
+    )?;
+    let updated_user = UpdatedUser::new(input.email, input.password);
+    let (token, revoked) = {
+        let conn: &MysqlConnection = &context.pool.get().unwrap();
+        conn.transaction::<_, SrvError, _>(|| {
+            let updated_user = updated_user.update(user, conn)?;
+            let revoked = UserToken::revoke_all(&user.id, conn)?;
+            let token = Token::from_user(updated_user)?.save(&context.request_info, conn)?;
+            Ok((token, revoked))
+        })?
+    };
+    revocation::revoke_sessions(&context.redis_client, &revoked)?;
+    if updated_user.email_changed(user) {
+        if let Err(error) = send_verification_email(context, &token.user).await {
+            println!("{:?}", error);
         }
-        Err(e) => Err(SrvError::ValidationError(e.into())),
+    }
+    Ok(token)
+}
+
+/// Updates the profile of the authenticated User, unlike [`update_user`] the sessions are kept
+///
+/// # Arguments
+/// * `ctx` - The GraphQL Context
+/// * `input` - The profile fields that will be Updated
+pub fn update_profile(ctx: &GqlContext<'_>, input: UserProfileInput) -> Result<User, SrvError> {
+    let context = ctx.data::<Context>();
+    let user = assert_user(context.user())?;
+    input.validate()?;
+    let conn: &MysqlConnection = &context.pool.get().unwrap();
+    let previous = UserProfile::find(&user.id, conn)?;
+    let changes = input.changes();
+    let replaces_avatar = changes.avatar_key.is_some();
+    changes.save(&user.id, conn)?;
+    if replaces_avatar {
+        remove_avatar_files(context, previous.and_then(|profile| profile.avatar_key));
+    }
+    Ok(user.clone())
+}
+
+/// Stores a new avatar for the authenticated User, the previous uploaded avatar is removed
+///
+/// # Arguments
+/// * `ctx` - The GraphQL Context
+/// * `file` - The image sent as a GraphQL multipart request
+pub async fn upload_avatar(ctx: &GqlContext<'_>, file: Upload) -> Result<User, SrvError> {
+    let context = ctx.data::<Context>();
+    let user = assert_user(context.user())?;
+    let content_type = file.content_type().map(|value| value.to_string());
+    let upload = file.into_read();
+    let blob_store = context.blob_store.clone();
+    let user_id = user.id.clone();
+    // Decoding and resizing the image takes a while, so it doesn't run in the request thread
+    let prefix = web::block(move || {
+        let content = avatar::read_upload(upload)?;
+        avatar::store_avatar(&**blob_store, &user_id, content_type.as_deref(), &content)
+    })
+    .await?;
+    let conn: &MysqlConnection = &context.pool.get().unwrap();
+    let previous = UserProfile::find(&user.id, conn)?;
+    UpdatedUserProfile {
+        avatar_key: Some(Some(prefix)),
+        ..Default::default()
+    }
+    .save(&user.id, conn)?;
+    remove_avatar_files(context, previous.and_then(|profile| profile.avatar_key));
+    Ok(user.clone())
+}
+
+/// The profile already points to the new avatar, so a failure only leaves unused files behind
+fn remove_avatar_files(context: &Context, avatar_key: Option<String>) {
+    if let Some(avatar_key) = avatar_key {
+        if let Err(error) = context.blob_store.delete_prefix(&avatar_key) {
+            println!("{:?}", error);
+        }
+    }
+}
+
+/// Sends a Password Reset link to the E-mail, always succeeds so it doesn't reveal if the E-mail exists.
+/// Every request is counted by the brute-force counters, so the E-mail can't be flooded
+///
+/// # Arguments
+/// * `ctx` - The GraphQL Context
+/// * `user_email` - The E-mail of the User that forgot the password
+pub fn request_password_reset(ctx: &GqlContext<'_>, user_email: String) -> Result<bool, SrvError> {
+    let context = ctx.data::<Context>();
+    let throttle = Throttle::new(&context.redis_client, PASSWORD_RESET_THROTTLE);
+    let subjects = throttle_subjects(context, &user_email);
+    throttle.check(&subjects)?;
+    throttle.register_failure(&subjects)?;
+    let pool = context.pool.clone();
+    let mail_service = context.mail_service.clone();
+    // The lookup, the token and the delivery happen after the response, so its duration is the
+    // same whether the E-mail exists or not
+    actix_rt::spawn(async move {
+        let reset = web::block(move || {
+            let conn: &MysqlConnection = &pool.get().unwrap();
+            create_password_reset(&user_email, conn)
+        })
+        .await;
+        let (email, locale, link) = match reset {
+            Ok(Some(reset)) => reset,
+            Ok(None) => return,
+            Err(error) => {
+                println!("{:?}", error);
+                return;
+            }
+        };
+        if let Err(error) = mail_service
+            .send(
+                &email,
+                Template::PasswordReset,
+                locale.as_deref(),
+                &[("link", link.as_str())],
+            )
+            .await
+        {
+            println!("{:?}", error);
+        }
+    });
+    Ok(true)
+}
+
+/// Stores a reset token for the User of the E-mail, returning the E-mail, the locale and the link
+fn create_password_reset(
+    user_email: &str,
+    conn: &MysqlConnection,
+) -> Result<Option<(String, Option<String>, String)>, SrvError> {
+    let user = match User::find_by_email(user_email, conn)? {
+        Some(user) => user,
+        None => return Ok(None),
     };
+    let token = random_token();
+    NewPasswordResetToken::new(&user.id, hash_token(&token)).save(conn)?;
+    let locale = UserProfile::locale_of(&user.id, conn)?;
+    let link = format!("{}/reset-password?token={}", ENV.app_url, token);
+    Ok(Some((user.email, locale, link)))
+}
+
+/// Resets the password of a User with a single use reset token, every session of the User is revoked
+///
+/// # Arguments
+/// * `ctx` - The GraphQL Context
+/// * `input` - The reset token and the new password
+pub fn reset_password(ctx: &GqlContext<'_>, input: PasswordResetInput) -> Result<bool, SrvError> {
+    use crate::schema::users::dsl::{deleted, id, users};
+    let context = ctx.data::<Context>();
+    let conn: &MysqlConnection = &context.pool.get().unwrap();
+    let revoked = conn.transaction::<_, SrvError, _>(|| {
+        let reset_token = PasswordResetToken::find_valid(&hash_token(&input.token), conn)?
+            .ok_or_else(|| {
+                SrvError::Unauthorized(UnauthorizedInfo {
+                    data: String::from("Invalid Password Reset Token!"),
+                })
+            })?;
+        let user = users
+            .filter(id.eq(&reset_token.user_id))
+            .filter(deleted.eq(false))
+            .first::<User>(conn)?;
+        // The token stays usable when the new password is refused
+        validate_with_password_policy(
+            &input,
+            "new_password",
+            Some(&input.new_password),
+            &user.email,
+        )?;
+        PasswordResetToken::use_all_from_user(&reset_token.user_id, conn)?;
+        UpdatedUser::new(None, Some(input.new_password.clone())).update(&user, conn)?;
+        Ok(UserToken::revoke_all(&user.id, conn)?)
+    })?;
+    revocation::revoke_sessions(&context.redis_client, &revoked)?;
+    Ok(true)
+}
+
+/// Verifies the E-mail of a User with the signed token sent by E-mail
+///
+/// # Arguments
+/// * `ctx` - The GraphQL Context
+/// * `token` - The E-mail verification token
+pub fn verify_email(ctx: &GqlContext<'_>, token: String) -> Result<bool, SrvError> {
+    use crate::schema::users::dsl::{deleted, id, users};
+    let context = ctx.data::<Context>();
+    let invalid_token = || {
+        SrvError::Unauthorized(UnauthorizedInfo {
+            data: String::from("Invalid E-mail Verification Token!"),
+        })
+    };
+    let (verified_id, verified_email) =
+        decode_email_verification_token(&token).ok_or_else(invalid_token)?;
+    let conn: &MysqlConnection = &context.pool.get().unwrap();
+    let user = users
+        .filter(id.eq(verified_id))
+        .filter(deleted.eq(false))
+        .first::<User>(conn)?;
+    // The E-mail may have changed after the token was sent
+    if user.email != verified_email {
+        return Err(invalid_token());
+    }
+    if user.email_verified_at.is_none() {
+        user.mark_email_verified(conn)?;
+    }
+    Ok(true)
+}
+
+/// Sends the E-mail verification link again to the authenticated User
+pub async fn resend_verification_email(ctx: &GqlContext<'_>) -> Result<bool, SrvError> {
+    let context = ctx.data::<Context>();
+    let user = assert_user(context.user())?;
+    if user.email_verified_at.is_some() {
+        return Ok(false);
+    }
+    send_verification_email(context, user).await?;
+    Ok(true)
+}
+
+async fn send_verification_email(context: &Context, user: &User) -> Result<(), SrvError> {
+    let token = create_email_verification_token(&user.id, &user.email)
+        .ok_or(SrvError::InternalServerError)?;
+    let link = format!("{}/verify-email?token={}", ENV.app_url, token);
+    let locale = {
+        let conn: &MysqlConnection = &context.pool.get().unwrap();
+        UserProfile::locale_of(&user.id, conn)?
+    };
+    context
+        .mail_service
+        .send(
+            &user.email,
+            Template::EmailVerification,
+            locale.as_deref(),
+            &[("link", link.as_str())],
+        )
+        .await?;
+    Ok(())
+}
+
+fn assert_email_verification_policy(user: &User) -> Result<(), SrvError> {
+    match (ENV.email_verification_policy, user.email_verified_at) {
+        (EmailVerificationPolicy::Required, None) => {
+            Err(SrvError::Unauthorized(UnauthorizedInfo {
+                data: String::from("You need to verify your E-mail before logging in!"),
+            }))
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Revokes a session of the authenticated User
+///
+/// # Arguments
+/// * `ctx` - The GraphQL Context
+/// * `session_id` - The ID of the session
+pub fn revoke_session(ctx: &GqlContext<'_>, session_id: ID) -> Result<bool, SrvError> {
+    let context = ctx.data::<Context>();
+    let user = assert_user(context.user())?;
+    let conn: &MysqlConnection = &context.pool.get().unwrap();
+    let revoked = UserToken::revoke(&user.id, &session_id, conn)?;
+    if revoked {
+        revocation::revoke_sessions(&context.redis_client, &[session_id.to_string()])?;
+    }
+    Ok(revoked)
+}
+
+/// Revokes every session of the authenticated User except the current one, returns how many were revoked
+pub fn revoke_other_sessions(ctx: &GqlContext<'_>) -> Result<i32, SrvError> {
+    let context = ctx.data::<Context>();
+    let user = assert_user(context.user())?;
+    let conn: &MysqlConnection = &context.pool.get().unwrap();
+    let current_session = context.session_id().unwrap();
+    let revoked = UserToken::revoke_others(&user.id, current_session, conn)?;
+    revocation::revoke_sessions(&context.redis_client, &revoked)?;
+    Ok(revoked.len() as i32)
+}
+
+/// Removes the lockout and the failed login attempts of an account
+///
+/// # Arguments
+/// * `ctx` - The GraphQL Context
+/// * `account_email` - The E-mail of the locked account
+pub fn unlock_account(ctx: &GqlContext<'_>, account_email: String) -> Result<bool, SrvError> {
+    let context = ctx.data::<Context>();
+    for action in [
+        LOGIN_THROTTLE,
+        mfa::MFA_THROTTLE,
+        login_link::LOGIN_LINK_THROTTLE,
+        PASSWORD_RESET_THROTTLE,
+    ]
+    .iter()
+    {
+        Throttle::new(&context.redis_client, *action).reset(&[Subject::Account(&account_email)])?;
+    }
+    Ok(true)
+}
+
+/// Marks the User as deleted and revokes all of its sessions
+fn soft_delete_user(
+    context: &Context,
+    user: &User,
+    details: Option<String>,
+) -> Result<(), SrvError> {
+    let conn: &MysqlConnection = &context.pool.get().unwrap();
+    let revoked = conn.transaction::<_, SrvError, _>(|| {
+        user.set_deleted(true, conn)?;
+        let revoked = UserToken::revoke_all(&user.id, conn)?;
+        audit::record(
+            context,
+            AuditEvent::AccountDeleted,
+            Some(&user.id),
+            details,
+            conn,
+        )?;
+        Ok(revoked)
+    })?;
+    revocation::revoke_sessions(&context.redis_client, &revoked)?;
+    Ok(())
+}
+
+/// Deletes the account of the authenticated User, the password is required when the User has one
+///
+/// # Arguments
+/// * `ctx` - The GraphQL Context
+/// * `password` - The current password of the User
+pub fn delete_my_account(ctx: &GqlContext<'_>, password: Option<String>) -> Result<bool, SrvError> {
+    let context = ctx.data::<Context>();
+    let user = assert_user(context.user())?;
+    if user.has_password() {
+        // A wrong password counts against the same lockout as a failed login
+        let throttle = Throttle::new(&context.redis_client, LOGIN_THROTTLE);
+        let subjects = throttle_subjects(context, &user.email);
+        throttle.check(&subjects)?;
+        if !password.map_or(false, |password| user.check_password(&password)) {
+            throttle.register_failure(&subjects)?;
+            return Err(SrvError::Unauthorized(UnauthorizedInfo {
+                data: String::from("Invalid Credentials!"),
+            }));
+        }
+        throttle.reset(&[Subject::Account(&user.email)])?;
+    }
+    soft_delete_user(context, user, None)?;
+    Ok(true)
+}
+
+/// Deletes the account of any User
+///
+/// # Arguments
+/// * `ctx` - The GraphQL Context
+/// * `user_id` - The id of the User
+pub fn delete_user(ctx: &GqlContext<'_>, user_id: ID) -> Result<bool, SrvError> {
+    let context = ctx.data::<Context>();
+    let conn: &MysqlConnection = &context.pool.get().unwrap();
+    let user = User::find(&user_id, conn)?.ok_or(SrvError::NotFound)?;
+    let details = context.actor().map(|actor| format!("by {}", actor));
+    soft_delete_user(context, &user, details)?;
+    Ok(true)
+}
+
+/// Restores a deleted account, the User has to login again
+///
+/// # Arguments
+/// * `ctx` - The GraphQL Context
+/// * `user_id` - The id of the deleted User
+pub fn restore_user(ctx: &GqlContext<'_>, user_id: ID) -> Result<User, SrvError> {
+    let context = ctx.data::<Context>();
+    let conn: &MysqlConnection = &context.pool.get().unwrap();
+    conn.transaction::<_, SrvError, _>(|| {
+        let user = User::find_with_deleted(&user_id, conn)?
+            .filter(|user| user.deleted)
+            .ok_or(SrvError::NotFound)?;
+        let details = context.actor().map(|actor| format!("by {}", actor));
+        audit::record(
+            context,
+            AuditEvent::AccountRestored,
+            Some(&user.id),
+            details,
+            conn,
+        )?;
+        Ok(user.set_deleted(false, conn)?)
+    })
 }
@@ -0,0 +1,65 @@
+use crate::errors::SrvError;
+use crate::graphql::context::Context as Ctx;
+use crate::models::{AuthItemModel, AuthItemType as ItemType};
+use async_graphql::{Context, Enum, FieldError};
+use chrono::*;
+use diesel::prelude::*;
+
+pub type AuthItem = AuthItemModel;
+
+#[async_graphql::Object(desc = "A role or a permission, roles are assigned to Users")]
+impl AuthItem {
+    pub async fn name(&self) -> &str {
+        &self.name
+    }
+    #[field(name = "type")]
+    pub async fn kind(&self) -> AuthItemType {
+        self.item_type().into()
+    }
+    pub async fn description(&self) -> &Option<String> {
+        &self.description
+    }
+    pub async fn created_at(&self) -> DateTime<Utc> {
+        DateTime::<Utc>::from_utc(self.created_at, Utc)
+    }
+    pub async fn updated_at(&self) -> DateTime<Utc> {
+        DateTime::<Utc>::from_utc(self.updated_at, Utc)
+    }
+    /// The items granted by this item
+    #[field(name = "children")]
+    pub async fn child_items(&self, ctx: &Context<'_>) -> Result<Vec<AuthItem>, FieldError> {
+        let conn: &MysqlConnection = &ctx.data::<Ctx>().pool.get().unwrap();
+        Ok(self.children(conn).map_err(SrvError::from)?)
+    }
+    /// The items that grant this item
+    #[field(name = "parents")]
+    pub async fn parent_items(&self, ctx: &Context<'_>) -> Result<Vec<AuthItem>, FieldError> {
+        let conn: &MysqlConnection = &ctx.data::<Ctx>().pool.get().unwrap();
+        Ok(self.parents(conn).map_err(SrvError::from)?)
+    }
+}
+
+#[Enum(desc = "The kind of an authorization item")]
+#[derive(Debug, Copy, PartialEq, Clone)]
+pub enum AuthItemType {
+    Role,
+    Permission,
+}
+
+impl From<ItemType> for AuthItemType {
+    fn from(item_type: ItemType) -> Self {
+        match item_type {
+            ItemType::Role => AuthItemType::Role,
+            ItemType::Permission => AuthItemType::Permission,
+        }
+    }
+}
+
+impl From<AuthItemType> for ItemType {
+    fn from(item_type: AuthItemType) -> Self {
+        match item_type {
+            AuthItemType::Role => ItemType::Role,
+            AuthItemType::Permission => ItemType::Permission,
+        }
+    }
+}
@@ -0,0 +1,65 @@
+use async_graphql::{Enum, ID};
+use chrono::*;
+
+use crate::models::{DataExportModel, DataExportStatus as DEStatus};
+use crate::utils::env::ENV;
+use crate::web_utils::jwt::create_data_export_token;
+
+pub type DataExport = DataExportModel;
+
+#[async_graphql::Object(desc = "An archive with all the personal data of a User")]
+impl DataExport {
+    pub async fn id(&self) -> ID {
+        ID::from(&self.id)
+    }
+    /// The User whose data is exported
+    pub async fn user_id(&self) -> ID {
+        ID::from(&self.user_id)
+    }
+    pub async fn status(&self) -> DataExportStatus {
+        self.current_status().into()
+    }
+    pub async fn created_at(&self) -> DateTime<Utc> {
+        DateTime::<Utc>::from_utc(self.created_at, Utc)
+    }
+    pub async fn completed_at(&self) -> Option<DateTime<Utc>> {
+        self.completed_at
+            .map(|date| DateTime::<Utc>::from_utc(date, Utc))
+    }
+    /// When the archive can't be downloaded anymore
+    pub async fn expires_at(&self) -> Option<DateTime<Utc>> {
+        self.expires_at
+            .map(|date| DateTime::<Utc>::from_utc(date, Utc))
+    }
+    /// Signed link that downloads the archive without authentication, only set when it is ready
+    pub async fn download_url(&self) -> Option<String> {
+        if !self.is_downloadable() {
+            return None;
+        }
+        let token = create_data_export_token(&self.id, self.expires_at?)?;
+        Some(format!(
+            "{}/data-exports/{}?token={}",
+            ENV.api_url, self.id, token
+        ))
+    }
+}
+
+#[Enum(desc = "The status of a data export")]
+#[derive(Debug, Copy, PartialEq, Clone)]
+pub enum DataExportStatus {
+    Pending,
+    Ready,
+    Failed,
+    Expired,
+}
+
+impl From<DEStatus> for DataExportStatus {
+    fn from(status: DEStatus) -> Self {
+        match status {
+            DEStatus::Pending => DataExportStatus::Pending,
+            DEStatus::Ready => DataExportStatus::Ready,
+            DEStatus::Failed => DataExportStatus::Failed,
+            DEStatus::Expired => DataExportStatus::Expired,
+        }
+    }
+}
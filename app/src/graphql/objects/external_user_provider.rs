@@ -45,3 +45,13 @@ impl From<UProvider> for UserProvider {
         }
     }
 }
+
+impl From<UserProvider> for UProvider {
+    fn from(up: UserProvider) -> Self {
+        match up {
+            UserProvider::Apple => UProvider::Apple,
+            UserProvider::Facebook => UProvider::Facebook,
+            UserProvider::Google => UProvider::Google,
+        }
+    }
+}
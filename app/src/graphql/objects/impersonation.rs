@@ -0,0 +1,12 @@
+use crate::graphql::objects::user::User;
+use async_graphql::SimpleObject;
+use chrono::*;
+
+#[SimpleObject(desc = "A short lived token of an admin acting as a User")]
+pub struct Impersonation {
+    #[field(desc = "Access token of the impersonated User, it can't be refreshed")]
+    pub token: String,
+    pub expires_at: DateTime<Utc>,
+    #[field(desc = "The impersonated User")]
+    pub user: User,
+}
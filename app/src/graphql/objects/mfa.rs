@@ -0,0 +1,26 @@
+use crate::graphql::objects::user::Token;
+use async_graphql::SimpleObject;
+use chrono::*;
+
+#[SimpleObject(desc = "A pending Two-Factor Authentication enrollment")]
+pub struct MfaEnrollment {
+    #[field(desc = "Base32 secret for authenticator apps without QR code support")]
+    pub secret: String,
+    #[field(desc = "The otpauth URI that can be shown as a QR code")]
+    pub otpauth_uri: String,
+}
+
+#[SimpleObject(desc = "A login that must be completed with a Two-Factor Authentication code")]
+pub struct MfaChallenge {
+    #[field(desc = "Challenge exchanged for a Token by the verifyMfa mutation")]
+    pub challenge: String,
+    pub expires_at: DateTime<Utc>,
+}
+
+#[async_graphql::Union(
+    desc = "The result of a login, a Token or a Two-Factor Authentication challenge"
+)]
+pub enum LoginResult {
+    Token(Token),
+    MfaChallenge(MfaChallenge),
+}
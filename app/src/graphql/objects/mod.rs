@@ -1,2 +1,10 @@
+pub mod auth_item;
+pub mod data_export;
 pub mod external_user_provider;
+pub mod impersonation;
+pub mod mfa;
+pub mod oauth_client;
+pub mod personal_access_token;
+pub mod session;
 pub mod user;
+pub mod user_profile;
@@ -0,0 +1,44 @@
+use crate::models::OAuthClientModel;
+use async_graphql::{SimpleObject, ID};
+use chrono::*;
+
+pub type OAuthClient = OAuthClientModel;
+
+#[async_graphql::Object(
+    desc = "A service that authenticates with the OAuth2 client credentials grant"
+)]
+impl OAuthClient {
+    /// The `client_id` of the token requests
+    pub async fn id(&self) -> ID {
+        ID::from(&self.id)
+    }
+    pub async fn name(&self) -> &str {
+        &self.name
+    }
+    /// The scopes that the client can request
+    pub async fn scopes(&self) -> Vec<String> {
+        self.allowed_scopes()
+            .iter()
+            .map(|scope| scope.as_str().to_string())
+            .collect()
+    }
+    /// The admin that registered the client
+    pub async fn created_by(&self) -> Option<ID> {
+        self.created_by.as_ref().map(ID::from)
+    }
+    pub async fn created_at(&self) -> DateTime<Utc> {
+        DateTime::<Utc>::from_utc(self.created_at, Utc)
+    }
+    /// When set the client can't request tokens anymore
+    pub async fn revoked_at(&self) -> Option<DateTime<Utc>> {
+        self.revoked_at
+            .map(|date| DateTime::<Utc>::from_utc(date, Utc))
+    }
+}
+
+#[SimpleObject(desc = "A registered OAuth2 client with its secret")]
+pub struct OAuthClientCredentials {
+    pub client: OAuthClient,
+    #[field(desc = "The `client_secret` of the token requests, it is only shown here")]
+    pub client_secret: String,
+}
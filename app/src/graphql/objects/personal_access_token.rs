@@ -0,0 +1,40 @@
+use crate::models::PersonalAccessTokenModel;
+use async_graphql::{SimpleObject, ID};
+use chrono::*;
+
+pub type PersonalAccessToken = PersonalAccessTokenModel;
+
+#[async_graphql::Object(desc = "A long lived token of a User that is not tied to a session")]
+impl PersonalAccessToken {
+    pub async fn id(&self) -> ID {
+        ID::from(&self.id)
+    }
+    pub async fn name(&self) -> &str {
+        &self.name
+    }
+    /// What the token allows, limited by the roles of the User
+    pub async fn scopes(&self) -> Vec<String> {
+        self.allowed_scopes()
+            .iter()
+            .map(|scope| scope.as_str().to_string())
+            .collect()
+    }
+    pub async fn expires_at(&self) -> Option<DateTime<Utc>> {
+        self.expires_at
+            .map(|date| DateTime::<Utc>::from_utc(date, Utc))
+    }
+    pub async fn last_used_at(&self) -> Option<DateTime<Utc>> {
+        self.last_used_at
+            .map(|date| DateTime::<Utc>::from_utc(date, Utc))
+    }
+    pub async fn created_at(&self) -> DateTime<Utc> {
+        DateTime::<Utc>::from_utc(self.created_at, Utc)
+    }
+}
+
+#[SimpleObject(desc = "A created personal access token with its secret")]
+pub struct PersonalAccessTokenCredentials {
+    pub personal_access_token: PersonalAccessToken,
+    #[field(desc = "The Bearer token of the requests, it is only shown here")]
+    pub token: String,
+}
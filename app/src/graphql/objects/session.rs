@@ -0,0 +1,96 @@
+use crate::graphql::context::Context as Ctx;
+use crate::models::UserTokenModel;
+use async_graphql::connection::{Connection as GqlConn, DataSource, Edge, EmptyFields};
+use async_graphql::{Context, DataSource, FieldResult, ID};
+use chrono::*;
+use diesel::prelude::*;
+
+pub type Session = UserTokenModel;
+
+#[async_graphql::Object(desc = "A session created by a login")]
+impl Session {
+    pub async fn id(&self) -> ID {
+        ID::from(&self.id)
+    }
+    /// If this is the session of the current request
+    pub async fn current(&self, ctx: &Context<'_>) -> bool {
+        ctx.data::<Ctx>().session_id() == Some(&self.id)
+    }
+    /// User agent of the client that logged in
+    pub async fn user_agent(&self) -> &Option<String> {
+        &self.user_agent
+    }
+    /// IP address of the client that logged in
+    pub async fn ip_address(&self) -> &Option<String> {
+        &self.ip_address
+    }
+    pub async fn created_at(&self) -> DateTime<Utc> {
+        DateTime::<Utc>::from_utc(self.created_at, Utc)
+    }
+    /// Last time the tokens of this session were refreshed
+    pub async fn last_refreshed_at(&self) -> Option<DateTime<Utc>> {
+        self.last_refreshed_at
+            .map(|date| DateTime::<Utc>::from_utc(date, Utc))
+    }
+}
+
+/// The sessions of a User, newest first
+pub struct SessionConnection {
+    pub user_id: String,
+}
+
+const MAX_PAGE_SIZE: usize = 100;
+
+pub type SessionConnResult = FieldResult<GqlConn<ID, Session, EmptyFields, EmptyFields>>;
+
+#[DataSource]
+impl DataSource for SessionConnection {
+    type CursorType = ID;
+    type NodeType = Session;
+    type ConnectionFieldsType = EmptyFields;
+    type EdgeFieldsType = EmptyFields;
+
+    async fn execute_query(
+        &self,
+        ctx: &Context<'_>,
+        after: Option<ID>,
+        before: Option<ID>,
+        first: Option<usize>,
+        last: Option<usize>,
+    ) -> SessionConnResult {
+        use crate::schema::user_tokens::dsl::*;
+        let context = ctx.data::<Ctx>();
+        let conn: &MysqlConnection = &context.pool.get().unwrap();
+        let mut query = user_tokens
+            .filter(user_id.eq(&self.user_id))
+            .filter(refresh_expire_at.ge(Utc::now().naive_local()))
+            .into_boxed();
+        if let Some(after) = &after {
+            query = query.filter(id.lt(after.to_string()));
+        }
+        if let Some(before) = &before {
+            query = query.filter(id.gt(before.to_string()));
+        }
+        // `last` takes the oldest sessions of the range, they are reversed back below
+        let limit = first.or(last).unwrap_or(MAX_PAGE_SIZE).min(MAX_PAGE_SIZE);
+        query = match (first, last) {
+            (None, Some(_)) => query.order(id.asc()),
+            _ => query.order(id.desc()),
+        };
+        let mut sessions: Vec<Session> = query.limit(limit as i64 + 1).load(conn)?;
+        let has_more = sessions.len() > limit;
+        sessions.truncate(limit);
+        let (has_previous, has_next) = match (first, last) {
+            (None, Some(_)) => {
+                sessions.reverse();
+                (has_more, before.is_some())
+            }
+            _ => (after.is_some(), has_more),
+        };
+        let mut connection = GqlConn::new(has_previous, has_next);
+        connection.append(sessions.into_iter().map(|session| {
+            Edge::with_additional_fields(ID::from(session.id.clone()), session, EmptyFields)
+        }));
+        Ok(connection)
+    }
+}
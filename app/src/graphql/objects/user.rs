@@ -1,16 +1,35 @@
+use crate::errors::{SrvError, UnauthorizedInfo};
 use crate::graphql::context::Context as Ctx;
+use crate::graphql::guards::{Role, Scope};
+use crate::graphql::objects::session::{SessionConnResult, SessionConnection};
+use crate::graphql::objects::user_profile::{AvatarSize, UserProfile};
 use crate::models::external_user_provider::ExternalUserProviderModel as ExternalUserProvider;
 use crate::models::{
     NewUserTokenModel as NewUserToken, UpdatedUserModel as UpdatedUser, UserModel,
 };
-use crate::{errors::SrvError, web_utils::jwt::create_token};
+use crate::utils::avatar::avatar_key;
+use crate::utils::token::hash_token;
+use crate::web_utils::jwt::create_token;
+use crate::web_utils::request::RequestInfo;
 use async_graphql::connection::{Connection as GqlConn, DataSource, Edge, EmptyFields};
 use async_graphql::{Context, DataSource, FieldError, FieldResult, ID};
 use chrono::*;
+use cuid::cuid;
 use diesel::prelude::*;
 use uuid::Uuid;
 pub type User = UserModel;
 
+/// The account data of a User is only visible to its sessions and to the admins
+fn assert_owner_or_admin(context: &Ctx, user_id: &str) -> Result<(), SrvError> {
+    let is_owner = context.session_user_id().map(|id| id.as_str()) == Some(user_id);
+    if !is_owner && !context.is_authorized(&Role::Admin, Some(Scope::UsersRead)) {
+        return Err(SrvError::Unauthorized(UnauthorizedInfo {
+            data: String::from("You are not Authorized to acess This!"),
+        }));
+    }
+    Ok(())
+}
+
 #[async_graphql::Object(desc = "A user")]
 impl User {
     pub async fn id(&self) -> ID {
@@ -19,16 +38,71 @@ impl User {
     pub async fn email(&self) -> &String {
         &self.email
     }
+    pub async fn email_verified(&self) -> bool {
+        self.email_verified_at.is_some()
+    }
+    /// If this account was deleted, only admins can see deleted Users
+    pub async fn deleted(&self) -> bool {
+        self.deleted
+    }
     pub async fn created_at(&self) -> DateTime<Utc> {
         DateTime::<Utc>::from_utc(self.created_at, Utc)
     }
     pub async fn updated_at(&self) -> DateTime<Utc> {
         DateTime::<Utc>::from_utc(self.updated_at, Utc)
     }
-    async fn providers(&self, ctx: &Context<'_>) -> Vec<ExternalUserProvider> {
+    /// Linked External Providers of this User, visible only to the User and to admins
+    async fn providers(&self, ctx: &Context<'_>) -> FieldResult<Vec<ExternalUserProvider>> {
         let context = ctx.data::<Ctx>();
+        assert_owner_or_admin(context, &self.id)?;
         let providers_dataloader = &context.dataloaders.e_user_by_user_id;
-        providers_dataloader.load(self.id.clone()).await
+        Ok(providers_dataloader.load(self.id.clone()).await)
+    }
+    async fn profile(&self, ctx: &Context<'_>) -> Option<UserProfile> {
+        let context = ctx.data::<Ctx>();
+        context
+            .dataloaders
+            .profile_by_user_id
+            .load(self.id.clone())
+            .await
+    }
+    /// The uploaded avatar in the requested size, `MEDIUM` by default, or the avatar URL of the profile
+    async fn avatar_url(&self, ctx: &Context<'_>, size: Option<AvatarSize>) -> Option<String> {
+        let context = ctx.data::<Ctx>();
+        let profile = context
+            .dataloaders
+            .profile_by_user_id
+            .load(self.id.clone())
+            .await?;
+        match &profile.avatar_key {
+            Some(prefix) => Some(context.blob_store.url(&avatar_key(
+                prefix,
+                size.unwrap_or(AvatarSize::Medium).pixels(),
+            ))),
+            None => profile.avatar_url,
+        }
+    }
+    /// Active sessions of this User, visible only to the User and to admins
+    async fn sessions(
+        &self,
+        ctx: &Context<'_>,
+        after: Option<ID>,
+        before: Option<ID>,
+        first: Option<i32>,
+        last: Option<i32>,
+    ) -> SessionConnResult {
+        assert_owner_or_admin(ctx.data::<Ctx>(), &self.id)?;
+        SessionConnection {
+            user_id: self.id.clone(),
+        }
+        .query(
+            ctx,
+            after.map(|val| val.to_string()),
+            before.map(|val| val.to_string()),
+            first,
+            last,
+        )
+        .await
     }
 }
 
@@ -37,6 +111,7 @@ pub struct Token {
     pub value: String,
     pub refresh_token: String,
     pub user: User,
+    pub session_id: String,
 }
 
 #[async_graphql::Object(desc = "The token object with user information")]
@@ -56,29 +131,54 @@ impl Token {
 }
 
 impl Token {
+    /// Creates the Token of a new session
     pub fn from_user(user: User) -> Result<Token, SrvError> {
-        match create_token(&user.id) {
+        Self::for_session(user, cuid().unwrap())
+    }
+
+    /// Creates a Token for an existing session, used when its tokens are refreshed
+    pub fn for_session(user: User, session_id: String) -> Result<Token, SrvError> {
+        match create_token(&user.id, &session_id) {
             Some(value) => Ok(Token {
                 value,
                 refresh_token: Uuid::new_v4().to_string(),
                 user,
+                session_id,
             }),
             None => Err(SrvError::InternalServerError),
         }
     }
 
-    pub fn save(self, conn: &MysqlConnection) -> Result<Token, SrvError> {
+    /// Saves the Token as a new session, with the client information of the login request
+    pub fn save(
+        self,
+        request_info: &RequestInfo,
+        conn: &MysqlConnection,
+    ) -> Result<Token, SrvError> {
+        // Only the digests are stored, a database dump can't be used to take over the sessions
+        let (token_hash, refresh_token_hash) = self.hashes();
         NewUserToken::new(
-            Some(&self.value),
-            Some(&self.refresh_token),
+            self.session_id.clone(),
+            Some(&token_hash),
+            Some(&refresh_token_hash),
             Some(&self.user.id),
+            request_info.user_agent.as_ref(),
+            request_info.ip.as_ref(),
         )
         .save(conn)?;
         Ok(self)
     }
+
+    /// The digests of the value and of the refresh token, the only form stored in the database
+    pub fn hashes(&self) -> (String, String) {
+        (hash_token(&self.value), hash_token(&self.refresh_token))
+    }
 }
 
-pub struct UserConnection;
+/// The Users, the deleted ones are only listed when asked for
+pub struct UserConnection {
+    pub include_deleted: bool,
+}
 
 const MAX_PAGE_SIZE: i64 = 100;
 
@@ -103,10 +203,13 @@ impl DataSource for UserConnection {
         last: Option<usize>,
     ) -> UserConnResult {
         use crate::models::utils::pagination::{Paginate, PaginatedData};
-        use crate::schema::users::{all_columns, id, table};
+        use crate::schema::users::{all_columns, deleted, id, table};
         let context = ctx.data::<Ctx>();
         let conn: &MysqlConnection = &context.pool.get().unwrap();
         let mut query = table.into_boxed();
+        if !self.include_deleted {
+            query = query.filter(deleted.eq(false));
+        }
         let PaginatedData {
             data,
             total_pages,
@@ -0,0 +1,50 @@
+use async_graphql::Enum;
+use chrono::*;
+
+use crate::models::UserProfileModel;
+use crate::utils::avatar::AVATAR_SIZES;
+
+pub type UserProfile = UserProfileModel;
+
+#[async_graphql::Object(desc = "The public profile of a user")]
+impl UserProfile {
+    pub async fn display_name(&self) -> &Option<String> {
+        &self.display_name
+    }
+    /// BCP 47 language tag, also used for the E-mails
+    pub async fn locale(&self) -> &Option<String> {
+        &self.locale
+    }
+    /// IANA time zone
+    pub async fn timezone(&self) -> &Option<String> {
+        &self.timezone
+    }
+    /// The avatar URL set in the profile or shared by a provider, `User.avatarUrl` also has the uploaded avatar
+    pub async fn avatar_url(&self) -> &Option<String> {
+        &self.avatar_url
+    }
+    pub async fn updated_at(&self) -> DateTime<Utc> {
+        DateTime::<Utc>::from_utc(self.updated_at, Utc)
+    }
+}
+
+#[Enum(desc = "The sizes of the uploaded avatars")]
+#[derive(Debug, Copy, PartialEq, Clone)]
+pub enum AvatarSize {
+    /// 64x64 pixels
+    Small,
+    /// 128x128 pixels
+    Medium,
+    /// 256x256 pixels
+    Large,
+}
+
+impl AvatarSize {
+    pub fn pixels(self) -> u32 {
+        match self {
+            AvatarSize::Small => AVATAR_SIZES[0],
+            AvatarSize::Medium => AVATAR_SIZES[1],
+            AvatarSize::Large => AVATAR_SIZES[2],
+        }
+    }
+}
@@ -0,0 +1,30 @@
+use crate::errors::SrvError;
+use crate::graphql::context::Context;
+use crate::graphql::objects::auth_item::{AuthItem, AuthItemType};
+use async_graphql::Context as GqlContext;
+use diesel::prelude::*;
+
+/// The roles and the permissions, optionally only the ones of a type
+///
+/// # Arguments
+/// * `ctx` - The GraphQL Context
+/// * `kind` - The type of the items
+pub fn auth_items(
+    ctx: &GqlContext<'_>,
+    kind: Option<AuthItemType>,
+) -> Result<Vec<AuthItem>, SrvError> {
+    let context = ctx.data::<Context>();
+    let conn: &MysqlConnection = &context.pool.get().unwrap();
+    Ok(AuthItem::all(kind.map(|kind| kind.into()), conn)?)
+}
+
+/// Finds a role or a permission by its name
+///
+/// # Arguments
+/// * `ctx` - The GraphQL Context
+/// * `name` - The name of the item
+pub fn auth_item(ctx: &GqlContext<'_>, name: String) -> Result<Option<AuthItem>, SrvError> {
+    let context = ctx.data::<Context>();
+    let conn: &MysqlConnection = &context.pool.get().unwrap();
+    Ok(AuthItem::find(&name, conn)?)
+}
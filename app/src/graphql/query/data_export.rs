@@ -0,0 +1,19 @@
+use crate::errors::SrvError;
+use crate::graphql::context::Context;
+use crate::graphql::guards::{Role, Scope};
+use crate::graphql::objects::data_export::DataExport;
+use async_graphql::{Context as GqlContext, ID};
+use diesel::prelude::*;
+
+/// Finds a data export, only its requester and admins can see it
+///
+/// # Arguments
+/// * `ctx` - The GraphQL Context
+/// * `id` - The id of the export
+pub fn data_export(ctx: &GqlContext<'_>, id: ID) -> Result<Option<DataExport>, SrvError> {
+    let context = ctx.data::<Context>();
+    let conn: &MysqlConnection = &context.pool.get().unwrap();
+    let is_admin = context.is_authorized(&Role::Admin, Some(Scope::UsersRead));
+    Ok(DataExport::find(&id, conn)?
+        .filter(|export| is_admin || context.session_user_id() == Some(&export.requested_by)))
+}
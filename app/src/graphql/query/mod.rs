@@ -1,6 +1,17 @@
+pub mod auth_item;
+pub mod data_export;
+pub mod oauth_client;
+pub mod personal_access_token;
 pub mod user;
 
-use crate::graphql::{guards::*, objects::user::UserConnResult};
+use crate::graphql::guards::*;
+use crate::graphql::objects::{
+    auth_item::{AuthItem, AuthItemType},
+    data_export::DataExport,
+    oauth_client::OAuthClient,
+    personal_access_token::PersonalAccessToken,
+    user::UserConnResult,
+};
 use async_graphql::guard::Guard;
 use async_graphql::*;
 
@@ -9,12 +20,12 @@ pub struct QueryRoot;
 
 #[async_graphql::Object]
 impl QueryRoot {
-    #[field(guard(AuthGuard()))]
+    #[field(guard(AccessGuard(role = "Role::User", scope = "Scope::ProfileRead")))]
     pub async fn me(&self, ctx: &Context<'_>) -> Option<User> {
         user::me(ctx)
     }
 
-    #[field(guard(RoleGuard(role = "Role::Admin")))]
+    #[field(guard(AccessGuard(role = "Role::Admin", scope = "Scope::UsersRead")))]
     pub async fn users(
         &self,
         ctx: &Context<'_>,
@@ -22,7 +33,48 @@ impl QueryRoot {
         before: Option<ID>,
         first: Option<i32>,
         last: Option<i32>,
+        include_deleted: Option<bool>,
     ) -> UserConnResult {
-        user::users(ctx, after, before, first, last).await
+        user::users(ctx, after, before, first, last, include_deleted).await
+    }
+
+    #[field(guard(AuthGuard()))]
+    pub async fn data_export(
+        &self,
+        ctx: &Context<'_>,
+        id: ID,
+    ) -> Result<Option<DataExport>, FieldError> {
+        Ok(data_export::data_export(ctx, id)?)
+    }
+
+    #[field(guard(RoleGuard(role = "Role::Admin")))]
+    pub async fn oauth_clients(&self, ctx: &Context<'_>) -> Result<Vec<OAuthClient>, FieldError> {
+        Ok(oauth_client::oauth_clients(ctx)?)
+    }
+
+    #[field(guard(AuthGuard()))]
+    pub async fn personal_access_tokens(
+        &self,
+        ctx: &Context<'_>,
+    ) -> Result<Vec<PersonalAccessToken>, FieldError> {
+        Ok(personal_access_token::personal_access_tokens(ctx)?)
+    }
+
+    #[field(guard(RoleGuard(role = "Role::Admin")))]
+    pub async fn auth_items(
+        &self,
+        ctx: &Context<'_>,
+        #[arg(name = "type")] kind: Option<AuthItemType>,
+    ) -> Result<Vec<AuthItem>, FieldError> {
+        Ok(auth_item::auth_items(ctx, kind)?)
+    }
+
+    #[field(guard(RoleGuard(role = "Role::Admin")))]
+    pub async fn auth_item(
+        &self,
+        ctx: &Context<'_>,
+        name: String,
+    ) -> Result<Option<AuthItem>, FieldError> {
+        Ok(auth_item::auth_item(ctx, name)?)
     }
 }
@@ -0,0 +1,15 @@
+use crate::errors::SrvError;
+use crate::graphql::context::Context;
+use crate::graphql::objects::oauth_client::OAuthClient;
+use async_graphql::Context as GqlContext;
+use diesel::prelude::*;
+
+/// Every registered OAuth2 client, the revoked ones included
+///
+/// # Arguments
+/// * `ctx` - The GraphQL Context
+pub fn oauth_clients(ctx: &GqlContext<'_>) -> Result<Vec<OAuthClient>, SrvError> {
+    let context = ctx.data::<Context>();
+    let conn: &MysqlConnection = &context.pool.get().unwrap();
+    Ok(OAuthClient::all(conn)?)
+}
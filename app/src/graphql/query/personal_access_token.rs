@@ -0,0 +1,17 @@
+use crate::errors::SrvError;
+use crate::graphql::context::Context;
+use crate::graphql::objects::personal_access_token::PersonalAccessToken;
+use crate::graphql::utils::authorization::assert_user;
+use async_graphql::Context as GqlContext;
+use diesel::prelude::*;
+
+/// The personal access tokens of the authenticated User that were not revoked
+///
+/// # Arguments
+/// * `ctx` - The GraphQL Context
+pub fn personal_access_tokens(ctx: &GqlContext<'_>) -> Result<Vec<PersonalAccessToken>, SrvError> {
+    let context = ctx.data::<Context>();
+    let user = assert_user(context.user())?;
+    let conn: &MysqlConnection = &context.pool.get().unwrap();
+    Ok(PersonalAccessToken::find_by_user(&user.id, conn)?)
+}
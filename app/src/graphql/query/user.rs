@@ -8,7 +8,7 @@ use diesel::prelude::*;
 
 pub fn me(ctx: &GqlContext) -> Option<User> {
     let ctx = ctx.data::<Context>();
-    ctx.user.clone()
+    ctx.user().clone()
 }
 
 pub async fn users(
@@ -17,14 +17,17 @@ pub async fn users(
     before: Option<ID>,
     first: Option<i32>,
     last: Option<i32>,
+    include_deleted: Option<bool>,
 ) -> UserConnResult {
-    UserConnection
-        .query(
-            ctx,
-            after.map(|val| val.to_string()),
-            before.map(|val| val.to_string()),
-            first,
-            last,
-        )
-        .await
+    UserConnection {
+        include_deleted: include_deleted.unwrap_or(false),
+    }
+    .query(
+        ctx,
+        after.map(|val| val.to_string()),
+        before.map(|val| val.to_string()),
+        first,
+        last,
+    )
+    .await
 }
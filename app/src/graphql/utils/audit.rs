@@ -0,0 +1,50 @@
+use crate::errors::SrvError;
+use crate::graphql::context::Context;
+use crate::models::{AuditEvent, NewAuditEventModel as NewAuditEvent};
+use diesel::mysql::MysqlConnection;
+
+/// Stores an audit event with the client information of the current request
+///
+/// # Arguments
+/// * `context` - The Context of the request
+/// * `event` - What happened
+/// * `user_id` - The User affected by the event
+/// * `details` - Free form details, never include secrets
+/// * `conn` - The connection, so the event is part of the caller transaction
+pub fn record(
+    context: &Context,
+    event: AuditEvent,
+    user_id: Option<&str>,
+    details: Option<String>,
+    conn: &MysqlConnection,
+) -> Result<(), SrvError> {
+    let mut audit_event = NewAuditEvent::new(event, user_id);
+    audit_event.ip_address = context.request_info.ip.as_ref();
+    audit_event.user_agent = context.request_info.user_agent.as_ref();
+    audit_event.details = details;
+    audit_event.save(conn)?;
+    Ok(())
+}
+
+/// Stores a mutation done while an admin impersonates a User, other requests are ignored
+///
+/// # Arguments
+/// * `context` - The Context of the request
+/// * `mutation` - The name of the mutation
+pub fn record_impersonated_mutation(context: &Context, mutation: &str) -> Result<(), SrvError> {
+    let impersonator_id = match context.impersonator_id() {
+        Some(impersonator_id) => impersonator_id,
+        None => return Ok(()),
+    };
+    let conn: &MysqlConnection = &context.pool.get().unwrap();
+    record(
+        context,
+        AuditEvent::ImpersonatedMutation,
+        context.user_id().map(|id| id.as_str()),
+        Some(format!(
+            "mutation: {}, by admin: {}",
+            mutation, impersonator_id
+        )),
+        conn,
+    )
+}
@@ -7,25 +7,40 @@ use crate::models::{
 use diesel::mysql::MysqlConnection;
 use diesel::prelude::*;
 use diesel::result::Error;
+use redis::Commands;
 use std::collections::hash_map::Entry;
 use std::collections::HashMap;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, Instant};
+
+// Bumped on every change of the items, so every server reloads them
+const VERSION_KEY: &str = "auth:items:version";
+// The version is read at most once in this interval instead of on every request
+const VERSION_CHECK_INTERVAL: Duration = Duration::from_secs(5);
+
+/// The items and their children as loaded from the database
+#[derive(Default)]
+struct AuthTree {
+    version: u64,
+    auth_items: HashMap<String, AuthItem>,
+    auth_relations: HashMap<String, Vec<String>>,
+}
 
 #[derive(Clone)]
 pub struct AuthorizationService {
-    pub auth_items: Arc<HashMap<String, AuthItem>>,
-    pub auth_relations: Arc<HashMap<String, Vec<String>>>,
+    tree: Arc<RwLock<AuthTree>>,
+    version_checked_at: Arc<Mutex<Option<Instant>>>,
     pub pool: Arc<DbPool>,
 }
 
-const USER_ITEM_NAME: &str = "user";
-const ADMIN_ITEM_NAME: &str = "admin";
+pub const USER_ITEM_NAME: &str = "user";
+pub const ADMIN_ITEM_NAME: &str = "admin";
 
 impl AuthorizationService {
     pub fn new(pool: Arc<DbPool>) -> AuthorizationService {
         AuthorizationService {
-            auth_items: Arc::new(HashMap::new()),
-            auth_relations: Arc::new(HashMap::new()),
+            tree: Arc::new(RwLock::new(AuthTree::default())),
+            version_checked_at: Arc::new(Mutex::new(None)),
             pool,
         }
     }
@@ -42,29 +57,66 @@ impl AuthorizationService {
         auth_item_children.load(conn)
     }
 
-    pub fn init(&mut self) -> Result<(), Error> {
+    pub fn init(&self) -> Result<(), Error> {
+        self.load(0)
+    }
+
+    fn load(&self, version: u64) -> Result<(), Error> {
         let items = self.auth_items_from_db()?;
         let item_children = self.auth_children_from_db()?;
-        let (auth_items, auth_relations) = {
-            let mut auth_items = HashMap::new();
-            for item in items {
-                auth_items.insert(item.name.clone(), item.into());
-            }
-            let mut auth_relations: HashMap<String, Vec<String>> = HashMap::new();
-            for item_child in item_children {
-                match auth_relations.entry(item_child.parent) {
-                    Entry::Occupied(o) => {
-                        o.into_mut().push(item_child.child);
-                    }
-                    Entry::Vacant(v) => {
-                        v.insert(vec![item_child.child]);
-                    }
-                };
-            }
-            (auth_items, auth_relations)
+        let mut auth_items = HashMap::new();
+        for item in items {
+            auth_items.insert(item.name.clone(), item);
+        }
+        let mut auth_relations: HashMap<String, Vec<String>> = HashMap::new();
+        for item_child in item_children {
+            match auth_relations.entry(item_child.parent) {
+                Entry::Occupied(o) => {
+                    o.into_mut().push(item_child.child);
+                }
+                Entry::Vacant(v) => {
+                    v.insert(vec![item_child.child]);
+                }
+            };
+        }
+        *self.tree.write().unwrap() = AuthTree {
+            version,
+            auth_items,
+            auth_relations,
         };
-        self.auth_items = Arc::new(auth_items);
-        self.auth_relations = Arc::new(auth_relations);
+        Ok(())
+    }
+
+    /// Reloads the items when another server changed them, the version is read from Redis at most
+    /// once every [`VERSION_CHECK_INTERVAL`]
+    pub fn reload_if_changed(&self, redis_client: &redis::Client) -> Result<(), SrvError> {
+        {
+            let mut checked_at = self.version_checked_at.lock().unwrap();
+            if checked_at.map_or(false, |checked_at| {
+                checked_at.elapsed() < VERSION_CHECK_INTERVAL
+            }) {
+                return Ok(());
+            }
+            *checked_at = Some(Instant::now());
+        }
+        let mut conn = redis_client.get_connection()?;
+        let version: Option<u64> = conn.get(VERSION_KEY)?;
+        let version = version.unwrap_or(0);
+        if self.tree.read().unwrap().version != version {
+            self.load(version)?;
+        }
+        Ok(())
+    }
+
+    /// Reloads the items after they were changed and tells the other servers to do the same
+    pub fn changed(&self, redis_client: &redis::Client) -> Result<(), SrvError> {
+        let version = redis_client
+            .get_connection()
+            .and_then(|mut conn| conn.incr::<_, _, u64>(VERSION_KEY, 1));
+        // Without Redis the other servers can't be told, but this one still sees the change
+        let current_version = self.tree.read().unwrap().version;
+        self.load(*version.as_ref().unwrap_or(&current_version))?;
+        version?;
         Ok(())
     }
 
@@ -87,15 +139,18 @@ impl AuthorizationService {
     }
 
     pub fn is_authorized(&self, assignments: &Option<Vec<AuthAssignment>>, action: String) -> bool {
+        let tree = self.tree.read().unwrap();
         match assignments {
             Some(assignments) => assignments.iter().fold(false, |acc, val| {
-                acc || self._is_authorized(&val.item_name, &action)
+                acc || tree.is_authorized(&val.item_name, &action)
             }),
             None => false,
         }
     }
+}
 
-    fn _is_authorized(&self, role: &String, action: &String) -> bool {
+impl AuthTree {
+    fn is_authorized(&self, role: &String, action: &String) -> bool {
         let auth_items = &self.auth_items;
         let auth_item = auth_items.get(role);
         return if let Some(auth_item) = auth_item {
@@ -1,2 +1,3 @@
 pub mod api_version;
+pub mod audit;
 pub mod authorization;
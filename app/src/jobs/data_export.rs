@@ -0,0 +1,148 @@
+//! Builds the personal data archives outside of the request that asked for them
+use crate::db::mysql::DbPool;
+use crate::models::{
+    AuditEventModel as AuditEvent, DataExportModel as DataExport, DataExportStatus,
+    ExternalUserProviderModel as ExternalUserProvider, UserMfaModel as UserMfa, UserModel as User,
+    UserProfileModel as UserProfile, UserTokenModel as UserToken,
+};
+use crate::utils::env::ENV;
+use chrono::{Duration, Utc};
+use diesel::mysql::MysqlConnection;
+use diesel::prelude::*;
+use serde_json::{json, Value};
+use std::error::Error;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::thread;
+
+const CLEAN_UP_INTERVAL_IN_MINUTES: u64 = 60;
+
+/// Builds the archive of an export in a background thread
+pub fn spawn(pool: Arc<DbPool>, export: DataExport) {
+    thread::spawn(move || {
+        let conn = match pool.get() {
+            Ok(conn) => conn,
+            Err(error) => return println!("{:?}", error),
+        };
+        if let Err(error) = run(&export, &conn) {
+            println!("{:?}", error);
+            export.mark(DataExportStatus::Failed, &conn).ok();
+        }
+    });
+}
+
+/// Removes the expired archives and fails the interrupted exports in a background thread, once
+/// at the start and then every [`CLEAN_UP_INTERVAL_IN_MINUTES`]
+pub fn schedule_clean_up(pool: Arc<DbPool>) {
+    thread::spawn(move || loop {
+        match pool.get() {
+            Ok(conn) => {
+                if let Err(error) = clean_up(&conn) {
+                    println!("{:?}", error);
+                }
+            }
+            Err(error) => println!("{:?}", error),
+        }
+        thread::sleep(std::time::Duration::from_secs(
+            CLEAN_UP_INTERVAL_IN_MINUTES * 60,
+        ));
+    });
+}
+
+/// Where the archive of an export is stored
+pub fn archive_path(export_id: &str) -> PathBuf {
+    PathBuf::from(&ENV.data_exports_dir).join(format!("{}.json", export_id))
+}
+
+fn run(export: &DataExport, conn: &MysqlConnection) -> Result<(), Box<dyn Error>> {
+    let archive = build_archive(&export.user_id, conn)?;
+    fs::create_dir_all(&ENV.data_exports_dir)?;
+    fs::write(
+        archive_path(&export.id),
+        serde_json::to_vec_pretty(&archive)?,
+    )?;
+    let expires_at = Utc::now().naive_utc() + Duration::hours(ENV.data_export_ttl_in_hours);
+    export.mark_ready(expires_at, conn)?;
+    Ok(())
+}
+
+/// Every row about the User, the password hashes and the secrets are never included
+fn build_archive(user_id: &str, conn: &MysqlConnection) -> Result<Value, diesel::result::Error> {
+    use crate::schema::{audit_events, external_user_providers, user_tokens};
+    let user = User::find_with_deleted(user_id, conn)?.ok_or(diesel::result::Error::NotFound)?;
+    let providers = external_user_providers::table
+        .filter(external_user_providers::user_id.eq(user_id))
+        .load::<ExternalUserProvider>(conn)?;
+    let assignments = user.auth_assignments(conn)?;
+    let sessions = user_tokens::table
+        .filter(user_tokens::user_id.eq(user_id))
+        .load::<UserToken>(conn)?;
+    let events = audit_events::table
+        .filter(audit_events::user_id.eq(user_id))
+        .order(audit_events::created_at.asc())
+        .load::<AuditEvent>(conn)?;
+    Ok(json!({
+        "generatedAt": Utc::now().naive_utc(),
+        "user": {
+            "id": user.id,
+            "email": user.email,
+            "emailVerifiedAt": user.email_verified_at,
+            "twoFactorEnabled": UserMfa::find_enabled(user_id, conn)?.is_some(),
+            "createdAt": user.created_at,
+            "updatedAt": user.updated_at,
+            "deleted": user.deleted,
+        },
+        "profile": UserProfile::find(user_id, conn)?.map(|profile| json!({
+            "displayName": profile.display_name,
+            "locale": profile.locale,
+            "timezone": profile.timezone,
+            "avatarUrl": profile.avatar_url,
+            "createdAt": profile.created_at,
+            "updatedAt": profile.updated_at,
+        })),
+        "providers": providers.iter().map(|provider| json!({
+            "id": provider.id,
+            "provider": format!("{:?}", provider.provider),
+            "externalId": provider.external_id,
+            "email": provider.email,
+            "createdAt": provider.created_at,
+            "updatedAt": provider.updated_at,
+            "deleted": provider.deleted,
+        })).collect::<Vec<_>>(),
+        "roles": assignments.iter().map(|assignment| json!({
+            "role": assignment.item_name,
+            "createdAt": assignment.created_at,
+        })).collect::<Vec<_>>(),
+        "sessions": sessions.iter().map(|session| json!({
+            "id": session.id,
+            "userAgent": session.user_agent,
+            "ipAddress": session.ip_address,
+            "createdAt": session.created_at,
+            "lastRefreshedAt": session.last_refreshed_at,
+        })).collect::<Vec<_>>(),
+        "auditEvents": events.iter().map(|event| json!({
+            "event": event.event,
+            "ipAddress": event.ip_address,
+            "userAgent": event.user_agent,
+            "details": event.details,
+            "createdAt": event.created_at,
+        })).collect::<Vec<_>>(),
+    }))
+}
+
+fn clean_up(conn: &MysqlConnection) -> Result<(), Box<dyn Error>> {
+    DataExport::fail_interrupted(conn)?;
+    remove_expired(conn)
+}
+
+/// Removes the archives whose download expired
+fn remove_expired(conn: &MysqlConnection) -> Result<(), Box<dyn Error>> {
+    for export in DataExport::find_expired(conn)? {
+        match fs::remove_file(archive_path(&export.id)) {
+            Err(error) if error.kind() != std::io::ErrorKind::NotFound => return Err(error.into()),
+            _ => export.mark(DataExportStatus::Expired, conn)?,
+        };
+    }
+    Ok(())
+}
@@ -0,0 +1,29 @@
+use crate::mail::{MailError, Mailer, Message};
+use std::sync::{Arc, Mutex};
+
+/// Keeps the sent messages in memory, clones share the same messages so tests can inspect them
+#[derive(Clone, Default)]
+pub struct MemoryMailer {
+    messages: Arc<Mutex<Vec<Message>>>,
+}
+
+impl MemoryMailer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn messages(&self) -> Vec<Message> {
+        self.messages.lock().unwrap().clone()
+    }
+
+    pub fn clear(&self) {
+        self.messages.lock().unwrap().clear();
+    }
+}
+
+impl Mailer for MemoryMailer {
+    fn send(&self, message: &Message) -> Result<(), MailError> {
+        self.messages.lock().unwrap().push(message.clone());
+        Ok(())
+    }
+}
@@ -0,0 +1,168 @@
+pub mod memory;
+pub mod outbox;
+pub mod smtp;
+pub mod templates;
+
+use crate::errors::SrvError;
+use crate::utils::env::ENV;
+use actix_web::{error::BlockingError, web};
+use lettre::SendableEmail;
+use lettre_email::EmailBuilder;
+use memory::MemoryMailer;
+use std::sync::Arc;
+pub use templates::Template;
+
+pub type ArcMailService = Arc<MailService>;
+
+/// A rendered E-mail ready to be delivered by a [`Mailer`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct Message {
+    pub from: String,
+    pub to: String,
+    pub subject: String,
+    pub html: String,
+    pub text: String,
+}
+
+impl Message {
+    /// Builds the MIME representation of this message with the HTML and the text alternatives
+    pub fn to_sendable(&self) -> Result<SendableEmail, MailError> {
+        EmailBuilder::new()
+            .from(self.from.as_str())
+            .to(self.to.as_str())
+            .subject(self.subject.as_str())
+            .alternative(self.html.as_str(), self.text.as_str())
+            .build()
+            .map(|email| email.into())
+            .map_err(|e| MailError::Message(e.to_string()))
+    }
+}
+
+#[derive(Debug)]
+pub enum MailError {
+    Message(String),
+    Transport(String),
+}
+
+impl From<MailError> for SrvError {
+    fn from(error: MailError) -> SrvError {
+        println!("{:?}", error);
+        SrvError::InternalServerError
+    }
+}
+
+/// A transport able to deliver E-mails
+pub trait Mailer: Send + Sync {
+    fn send(&self, message: &Message) -> Result<(), MailError>;
+}
+
+/// Renders the E-mail templates and delivers them with the configured [`Mailer`]
+pub struct MailService {
+    transport: Arc<dyn Mailer>,
+    from: String,
+    /// Set when the messages are kept in memory, so they can be inspected
+    memory: Option<MemoryMailer>,
+}
+
+impl MailService {
+    pub fn new(transport: Box<dyn Mailer>, from: String) -> Self {
+        Self {
+            transport: Arc::from(transport),
+            from,
+            memory: None,
+        }
+    }
+
+    /// A service that keeps every message in memory instead of delivering it
+    pub fn in_memory(from: String) -> Self {
+        let memory = MemoryMailer::new();
+        Self {
+            transport: Arc::new(memory.clone()),
+            from,
+            memory: Some(memory),
+        }
+    }
+
+    /// The messages sent by a service created with [`MailService::in_memory`], empty otherwise
+    pub fn sent_messages(&self) -> Vec<Message> {
+        self.memory
+            .as_ref()
+            .map(MemoryMailer::messages)
+            .unwrap_or_default()
+    }
+
+    /// Sends a template to an E-mail address, the delivery runs in the blocking thread pool
+    ///
+    /// # Arguments
+    /// * `to` - The recipient E-mail
+    /// * `template` - The template that is rendered
+    /// * `locale` - The preferred locale, the default locale is used when there is no variant for it
+    /// * `variables` - The values replacing the `{{ name }}` placeholders of the template
+    pub async fn send(
+        &self,
+        to: &str,
+        template: Template,
+        locale: Option<&str>,
+        variables: &[(&str, &str)],
+    ) -> Result<(), MailError> {
+        let rendered = template.render(locale, variables);
+        let message = Message {
+            from: self.from.clone(),
+            to: to.to_string(),
+            subject: rendered.subject,
+            html: rendered.html,
+            text: rendered.text,
+        };
+        let transport = self.transport.clone();
+        web::block(move || transport.send(&message))
+            .await
+            .map_err(|error| match error {
+                BlockingError::Error(error) => error,
+                BlockingError::Canceled => {
+                    MailError::Transport(String::from("The delivery was canceled"))
+                }
+            })
+    }
+}
+
+/// Creates the [`MailService`] using the transport configured by `MAIL_TRANSPORT`
+pub fn connect() -> MailService {
+    let transport: Box<dyn Mailer> = match ENV.mail_transport.as_str() {
+        "smtp" => Box::new(smtp::SmtpMailer::from_env()),
+        "memory" => return MailService::in_memory(ENV.mail_from.clone()),
+        _ => Box::new(outbox::OutboxMailer::new(&ENV.mail_outbox_dir)),
+    };
+    MailService::new(transport, ENV.mail_from.clone())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[actix_rt::test]
+    async fn in_memory_service_keeps_the_rendered_messages() {
+        let service = MailService::in_memory(String::from("noreply@example.com"));
+        service
+            .send(
+                "user@example.com",
+                Template::PasswordReset,
+                Some("pt-BR"),
+                &[("link", "https://example.com/reset?a=1&b=2")],
+            )
+            .await
+            .unwrap();
+        let messages = service.sent_messages();
+        assert_eq!(messages.len(), 1);
+        let expected = Template::PasswordReset
+            .render(Some("pt"), &[("link", "https://example.com/reset?a=1&b=2")]);
+        assert_eq!(messages[0].from, "noreply@example.com");
+        assert_eq!(messages[0].to, "user@example.com");
+        assert_eq!(messages[0].subject, expected.subject);
+        assert!(messages[0]
+            .text
+            .contains("https://example.com/reset?a=1&b=2"));
+        assert!(messages[0]
+            .html
+            .contains("https://example.com/reset?a=1&amp;b=2"));
+    }
+}
@@ -0,0 +1,34 @@
+use crate::mail::{MailError, Mailer, Message};
+use chrono::Utc;
+use std::fs;
+use std::path::PathBuf;
+
+/// Writes every message as a `.eml` file in a directory, useful for development
+pub struct OutboxMailer {
+    dir: PathBuf,
+}
+
+impl OutboxMailer {
+    pub fn new(dir: &str) -> Self {
+        Self {
+            dir: PathBuf::from(dir),
+        }
+    }
+}
+
+impl Mailer for OutboxMailer {
+    fn send(&self, message: &Message) -> Result<(), MailError> {
+        let sendable = message.to_sendable()?;
+        let file_name = format!(
+            "{}-{}.eml",
+            Utc::now().format("%Y%m%d%H%M%S"),
+            sendable.message_id()
+        );
+        let content = sendable
+            .message_to_string()
+            .map_err(|e| MailError::Message(e.to_string()))?;
+        fs::create_dir_all(&self.dir)
+            .and_then(|_| fs::write(self.dir.join(file_name), content))
+            .map_err(|e| MailError::Transport(e.to_string()))
+    }
+}
@@ -0,0 +1,56 @@
+use crate::mail::{MailError, Mailer, Message};
+use crate::utils::env::ENV;
+use lettre::smtp::authentication::Credentials;
+use lettre::{ClientSecurity, ClientTlsParameters, SmtpClient, Transport};
+use native_tls::TlsConnector;
+
+/// Delivers the messages to a SMTP server
+pub struct SmtpMailer {
+    host: String,
+    port: u16,
+    security: String,
+    credentials: Option<(String, String)>,
+}
+
+impl SmtpMailer {
+    pub fn from_env() -> Self {
+        let credentials = if ENV.smtp_username.is_empty() {
+            None
+        } else {
+            Some((ENV.smtp_username.clone(), ENV.smtp_password.clone()))
+        };
+        Self {
+            host: ENV.smtp_host.clone(),
+            port: ENV.smtp_port,
+            security: ENV.smtp_security.clone(),
+            credentials,
+        }
+    }
+
+    fn client_security(&self) -> Result<ClientSecurity, MailError> {
+        if self.security == "none" {
+            return Ok(ClientSecurity::None);
+        }
+        let connector = TlsConnector::new().map_err(|e| MailError::Transport(e.to_string()))?;
+        let parameters = ClientTlsParameters::new(self.host.clone(), connector);
+        Ok(match self.security.as_str() {
+            "tls" => ClientSecurity::Wrapper(parameters),
+            _ => ClientSecurity::Required(parameters),
+        })
+    }
+}
+
+impl Mailer for SmtpMailer {
+    fn send(&self, message: &Message) -> Result<(), MailError> {
+        let mut client = SmtpClient::new((self.host.as_str(), self.port), self.client_security()?)
+            .map_err(|e| MailError::Transport(e.to_string()))?;
+        if let Some((username, password)) = &self.credentials {
+            client = client.credentials(Credentials::new(username.clone(), password.clone()));
+        }
+        client
+            .transport()
+            .send(message.to_sendable()?)
+            .map(|_| ())
+            .map_err(|e| MailError::Transport(e.to_string()))
+    }
+}
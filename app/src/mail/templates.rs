@@ -0,0 +1,106 @@
+/// The locale used when a template has no variant for the requested one
+pub const DEFAULT_LOCALE: &str = "en";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Template {
+    PasswordReset,
+    EmailVerification,
+    LoginLink,
+}
+
+struct TemplateSource {
+    subject: &'static str,
+    html: &'static str,
+    text: &'static str,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RenderedTemplate {
+    pub subject: String,
+    pub html: String,
+    pub text: String,
+}
+
+macro_rules! template_source {
+    ($name:literal, $locale:literal) => {
+        TemplateSource {
+            subject: include_str!(concat!(
+                "../../templates/mail/",
+                $name,
+                "/",
+                $locale,
+                "/subject.txt"
+            )),
+            html: include_str!(concat!(
+                "../../templates/mail/",
+                $name,
+                "/",
+                $locale,
+                "/body.html"
+            )),
+            text: include_str!(concat!(
+                "../../templates/mail/",
+                $name,
+                "/",
+                $locale,
+                "/body.txt"
+            )),
+        }
+    };
+}
+
+impl Template {
+    fn source(&self, locale: &str) -> Option<TemplateSource> {
+        use Template::*;
+        match (self, locale) {
+            (PasswordReset, "en") => Some(template_source!("password_reset", "en")),
+            (PasswordReset, "pt") => Some(template_source!("password_reset", "pt")),
+            (EmailVerification, "en") => Some(template_source!("email_verification", "en")),
+            (EmailVerification, "pt") => Some(template_source!("email_verification", "pt")),
+            (LoginLink, "en") => Some(template_source!("login_link", "en")),
+            (LoginLink, "pt") => Some(template_source!("login_link", "pt")),
+            _ => None,
+        }
+    }
+
+    /// Renders the variant for the locale, falling back to its language and then to [`DEFAULT_LOCALE`]
+    pub fn render(&self, locale: Option<&str>, variables: &[(&str, &str)]) -> RenderedTemplate {
+        let locale = locale.unwrap_or(DEFAULT_LOCALE);
+        let language = locale
+            .split(|c| c == '-' || c == '_')
+            .next()
+            .unwrap_or(locale);
+        let source = self
+            .source(locale)
+            .or_else(|| self.source(language))
+            .or_else(|| self.source(DEFAULT_LOCALE))
+            .expect("Every template must have a variant for the default locale");
+        RenderedTemplate {
+            subject: replace_variables(source.subject.trim(), variables, false),
+            html: replace_variables(source.html, variables, true),
+            text: replace_variables(source.text, variables, false),
+        }
+    }
+}
+
+fn replace_variables(content: &str, variables: &[(&str, &str)], escape: bool) -> String {
+    variables
+        .iter()
+        .fold(content.to_string(), |content, (name, value)| {
+            let value = if escape {
+                escape_html(value)
+            } else {
+                value.to_string()
+            };
+            content.replace(&format!("{{{{ {} }}}}", name), &value)
+        })
+}
+
+fn escape_html(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
+}
@@ -12,19 +12,28 @@ extern crate serde_derive;
 mod db;
 mod errors;
 mod graphql;
+mod jobs;
+mod mail;
 mod models;
 mod schema;
+mod storage;
 mod utils;
 mod web_utils;
 
 use actix_cors::Cors;
+use actix_files::Files;
 use actix_web::{guard, middleware, web, web::Data, App, HttpServer};
+use async_graphql::IntoQueryBuilderOpts;
 use listenfd::ListenFd;
 
 use crate::db::{mysql, redis};
+use crate::graphql::utils::authorization::AuthorizationService;
 use crate::graphql::{Mutation, QueryRoot, Schema, Subscription};
 use crate::utils::env::ENV;
-use crate::web_utils::handlers::{gql, gql_playground, gql_subscriptions};
+use crate::web_utils::handlers::{
+    data_export, gql, gql_playground, gql_subscriptions, jwks, oauth_token,
+};
+use crate::web_utils::signing_keys::KEYS;
 
 fn create_schema() -> Schema {
     Schema::build(QueryRoot, Mutation, Subscription).finish()
@@ -35,15 +44,35 @@ async fn main() -> std::io::Result<()> {
     std::env::set_var("RUST_LOG", "actix_web=info");
     env_logger::init();
 
+    // Invalid keys or the default secret outside of dev stop the server here
+    lazy_static::initialize(&KEYS);
     let schema = create_schema();
     let mut listenfd = ListenFd::from_env();
     let mysql_pool = Data::new(mysql::connect());
     let redis_conn = Data::new(redis::connect());
+    let mail_service = Data::new(mail::connect());
+    let blob_store = Data::new(storage::connect());
+    jobs::data_export::schedule_clean_up(mysql_pool.clone().into_inner());
+    // The roles are loaded once, and again only when they are changed
+    let auth_service = AuthorizationService::new(mysql_pool.clone().into_inner());
+    auth_service
+        .init()
+        .expect("Error in AuthorizationService Initialization");
+    let auth_service = Data::new(auth_service);
     let mut server = HttpServer::new(move || {
         App::new()
             .data(schema.clone())
             .app_data(mysql_pool.clone())
             .app_data(redis_conn.clone())
+            .app_data(mail_service.clone())
+            .app_data(blob_store.clone())
+            .app_data(auth_service.clone())
+            // The multipart requests are refused while they are read, before a large file is buffered
+            .app_data(IntoQueryBuilderOpts {
+                max_num_files: Some(1),
+                max_file_size: Some(ENV.avatar_max_size_in_bytes as usize),
+                ..Default::default()
+            })
             .wrap(middleware::Compress::default())
             .wrap(middleware::Logger::default())
             .wrap(
@@ -61,6 +90,13 @@ async fn main() -> std::io::Result<()> {
                     .to(gql_subscriptions),
             )
             .service(web::resource("/").guard(guard::Get()).to(gql_playground))
+            .service(web::resource("/.well-known/jwks.json").route(web::get().to(jwks)))
+            .service(web::resource("/data-exports/{id}").route(web::get().to(data_export)))
+            .service(web::resource("/oauth/token").route(web::post().to(oauth_token)))
+            .service(Files::new(
+                storage::local::UPLOADS_PATH,
+                &ENV.blob_store_dir,
+            ))
     });
 
     server = if let Some(tcp_listener) = listenfd.take_tcp_listener(0).unwrap() {
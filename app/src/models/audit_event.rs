@@ -0,0 +1,85 @@
+use crate::schema::audit_events;
+use chrono::NaiveDateTime;
+use cuid::cuid;
+use diesel::mysql::MysqlConnection;
+use diesel::prelude::*;
+use diesel::result::Error;
+
+/// The security relevant events stored in the audit log
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum AuditEvent {
+    RefreshTokenReuse,
+    AccountDeleted,
+    AccountRestored,
+    DataExportRequested,
+    OAuthClientCreated,
+    OAuthClientRevoked,
+    PersonalAccessTokenCreated,
+    PersonalAccessTokenRevoked,
+    ImpersonationStarted,
+    ImpersonationEnded,
+    ImpersonatedMutation,
+    AuthItemsChanged,
+}
+
+impl AuditEvent {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            AuditEvent::RefreshTokenReuse => "refresh_token_reuse",
+            AuditEvent::AccountDeleted => "account_deleted",
+            AuditEvent::AccountRestored => "account_restored",
+            AuditEvent::DataExportRequested => "data_export_requested",
+            AuditEvent::OAuthClientCreated => "oauth_client_created",
+            AuditEvent::OAuthClientRevoked => "oauth_client_revoked",
+            AuditEvent::PersonalAccessTokenCreated => "personal_access_token_created",
+            AuditEvent::PersonalAccessTokenRevoked => "personal_access_token_revoked",
+            AuditEvent::ImpersonationStarted => "impersonation_started",
+            AuditEvent::ImpersonationEnded => "impersonation_ended",
+            AuditEvent::ImpersonatedMutation => "impersonated_mutation",
+            AuditEvent::AuthItemsChanged => "auth_items_changed",
+        }
+    }
+}
+
+#[derive(Identifiable, Queryable, Clone, Debug)]
+#[table_name = "audit_events"]
+pub struct AuditEventModel {
+    pub id: String,
+    pub user_id: Option<String>,
+    pub event: String,
+    pub ip_address: Option<String>,
+    pub user_agent: Option<String>,
+    pub details: Option<String>,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Insertable)]
+#[table_name = "audit_events"]
+pub struct NewAuditEventModel<'a> {
+    pub id: String,
+    pub user_id: Option<&'a str>,
+    pub event: &'static str,
+    pub ip_address: Option<&'a String>,
+    pub user_agent: Option<&'a String>,
+    pub details: Option<String>,
+}
+
+impl<'a> NewAuditEventModel<'a> {
+    pub fn new(event: AuditEvent, user_id: Option<&'a str>) -> Self {
+        Self {
+            id: cuid().unwrap(),
+            user_id,
+            event: event.as_str(),
+            ip_address: None,
+            user_agent: None,
+            details: None,
+        }
+    }
+
+    pub fn save(self, conn: &MysqlConnection) -> Result<usize, Error> {
+        use crate::schema::audit_events::dsl::*;
+        diesel::insert_into(audit_events)
+            .values(&self)
+            .execute(conn)
+    }
+}
@@ -1,9 +1,35 @@
+use crate::schema::auth_items;
 use chrono::NaiveDateTime;
 use diesel::mysql::MysqlConnection;
 use diesel::prelude::*;
 use diesel::result::Error;
 
-#[derive(Associations, Queryable, Clone, Debug)]
+/// The kinds of items stored in the `type` column
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum AuthItemType {
+    Role,
+    Permission,
+}
+
+impl AuthItemType {
+    pub fn as_i16(self) -> i16 {
+        match self {
+            AuthItemType::Role => 1,
+            AuthItemType::Permission => 2,
+        }
+    }
+
+    pub fn from_i16(value: i16) -> Self {
+        match value {
+            2 => AuthItemType::Permission,
+            _ => AuthItemType::Role,
+        }
+    }
+}
+
+#[derive(Identifiable, Associations, Queryable, Clone, Debug)]
+#[primary_key(name)]
+#[table_name = "auth_items"]
 pub struct AuthItemModel {
     pub name: String,
     pub r#type: i16,
@@ -13,6 +39,27 @@ pub struct AuthItemModel {
 }
 
 impl AuthItemModel {
+    pub fn find(item_name: &str, conn: &MysqlConnection) -> Result<Option<AuthItemModel>, Error> {
+        use crate::schema::auth_items::dsl::*;
+        auth_items.filter(name.eq(item_name)).first(conn).optional()
+    }
+
+    pub fn all(
+        item_type: Option<AuthItemType>,
+        conn: &MysqlConnection,
+    ) -> Result<Vec<AuthItemModel>, Error> {
+        use crate::schema::auth_items::dsl::*;
+        let mut query = auth_items.order(name.asc()).into_boxed();
+        if let Some(item_type) = item_type {
+            query = query.filter(type_.eq(item_type.as_i16()));
+        }
+        query.load(conn)
+    }
+
+    pub fn item_type(&self) -> AuthItemType {
+        AuthItemType::from_i16(self.r#type)
+    }
+
     pub fn children(&self, conn: &MysqlConnection) -> Result<Vec<AuthItemModel>, Error> {
         use crate::schema::auth_item_children::dsl::{auth_item_children, child, parent};
         use crate::schema::auth_items::{
@@ -25,4 +72,73 @@ impl AuthItemModel {
             .select(all_columns)
             .load(conn)
     }
+
+    pub fn parents(&self, conn: &MysqlConnection) -> Result<Vec<AuthItemModel>, Error> {
+        use crate::schema::auth_item_children::dsl::{auth_item_children, child, parent};
+        use crate::schema::auth_items::{
+            all_columns,
+            dsl::{auth_items, name},
+        };
+        auth_items
+            .inner_join(auth_item_children.on(parent.eq(name)))
+            .filter(child.eq(self.name.clone()))
+            .select(all_columns)
+            .load(conn)
+    }
+
+    /// Deletes the item, its children edges and assignments are removed by the foreign keys
+    pub fn delete(&self, conn: &MysqlConnection) -> Result<usize, Error> {
+        diesel::delete(self).execute(conn)
+    }
+}
+
+#[derive(Insertable)]
+#[table_name = "auth_items"]
+pub struct NewAuthItemModel<'a> {
+    pub name: &'a str,
+    pub type_: i16,
+    pub description: Option<&'a str>,
+}
+
+impl<'a> NewAuthItemModel<'a> {
+    pub fn new(name: &'a str, item_type: AuthItemType, description: Option<&'a str>) -> Self {
+        Self {
+            name,
+            type_: item_type.as_i16(),
+            description,
+        }
+    }
+
+    pub fn save(self, conn: &MysqlConnection) -> Result<AuthItemModel, Error> {
+        use crate::schema::auth_items::dsl::*;
+        diesel::insert_into(auth_items)
+            .values(&self)
+            .execute(conn)?;
+        auth_items.filter(name.eq(self.name)).first(conn)
+    }
+}
+
+/// Changes of an item, `None` keeps a field and `Some(None)` clears the description
+#[derive(AsChangeset, Default, Clone, Debug)]
+#[table_name = "auth_items"]
+pub struct UpdatedAuthItemModel {
+    pub name: Option<String>,
+    pub type_: Option<i16>,
+    pub description: Option<Option<String>>,
+}
+
+impl UpdatedAuthItemModel {
+    /// Updates the item, a new name is cascaded to its edges and assignments by the foreign keys
+    pub fn update(
+        self,
+        item: &AuthItemModel,
+        conn: &MysqlConnection,
+    ) -> Result<AuthItemModel, Error> {
+        use crate::schema::auth_items::dsl::*;
+        let item_name = self.name.clone().unwrap_or_else(|| item.name.clone());
+        if self.name.is_some() || self.type_.is_some() || self.description.is_some() {
+            diesel::update(item).set(&self).execute(conn)?;
+        }
+        auth_items.filter(name.eq(item_name)).first(conn)
+    }
 }
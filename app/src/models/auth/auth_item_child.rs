@@ -1,5 +1,132 @@
+use crate::schema::auth_item_children;
+use diesel::mysql::MysqlConnection;
+use diesel::prelude::*;
+use diesel::result::Error;
+use std::collections::HashSet;
+
 #[derive(Associations, Queryable, Clone)]
 pub struct AuthItemChildModel {
     pub parent: String,
     pub child: String,
 }
+
+impl AuthItemChildModel {
+    pub fn find(
+        parent_name: &str,
+        child_name: &str,
+        conn: &MysqlConnection,
+    ) -> Result<Option<AuthItemChildModel>, Error> {
+        use crate::schema::auth_item_children::dsl::*;
+        auth_item_children
+            .filter(parent.eq(parent_name))
+            .filter(child.eq(child_name))
+            .first(conn)
+            .optional()
+    }
+
+    /// If `to` is `from` or one of its descendants, so an edge from `to` to `from` would be a cycle
+    ///
+    /// The edges are locked until the end of the transaction, so concurrent edges that would close
+    /// a cycle together are checked one after the other
+    pub fn reaches(from: &str, to: &str, conn: &MysqlConnection) -> Result<bool, Error> {
+        use crate::schema::auth_item_children::dsl::*;
+        let edges = auth_item_children
+            .for_update()
+            .load::<AuthItemChildModel>(conn)?;
+        Ok(Self::reaches_through(&edges, from, to))
+    }
+
+    fn reaches_through(edges: &[AuthItemChildModel], from: &str, to: &str) -> bool {
+        let mut visited = HashSet::new();
+        let mut pending = vec![from];
+        while let Some(item) = pending.pop() {
+            if item == to {
+                return true;
+            }
+            if visited.insert(item) {
+                pending.extend(
+                    edges
+                        .iter()
+                        .filter(|edge| edge.parent == item)
+                        .map(|edge| edge.child.as_str()),
+                );
+            }
+        }
+        false
+    }
+
+    pub fn delete(
+        parent_name: &str,
+        child_name: &str,
+        conn: &MysqlConnection,
+    ) -> Result<usize, Error> {
+        use crate::schema::auth_item_children::dsl::*;
+        diesel::delete(
+            auth_item_children
+                .filter(parent.eq(parent_name))
+                .filter(child.eq(child_name)),
+        )
+        .execute(conn)
+    }
+}
+
+#[derive(Insertable)]
+#[table_name = "auth_item_children"]
+pub struct NewAuthItemChildModel<'a> {
+    pub parent: &'a str,
+    pub child: &'a str,
+}
+
+impl<'a> NewAuthItemChildModel<'a> {
+    pub fn new(parent: &'a str, child: &'a str) -> Self {
+        Self { parent, child }
+    }
+
+    pub fn save(&self, conn: &MysqlConnection) -> Result<usize, Error> {
+        use crate::schema::auth_item_children::dsl::*;
+        diesel::insert_into(auth_item_children)
+            .values(self)
+            .execute(conn)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Checks `reaches` over the given parent and child edges
+    fn reaches(pairs: &[(&str, &str)], from: &str, to: &str) -> bool {
+        let edges: Vec<AuthItemChildModel> = pairs
+            .iter()
+            .map(|(parent, child)| AuthItemChildModel {
+                parent: parent.to_string(),
+                child: child.to_string(),
+            })
+            .collect();
+        AuthItemChildModel::reaches_through(&edges, from, to)
+    }
+
+    const ROLES: &[(&str, &str)] = &[
+        ("admin", "user"),
+        ("user", "updateProfile"),
+        ("admin", "deleteUser"),
+    ];
+
+    #[test]
+    fn an_item_reaches_itself_and_its_descendants() {
+        assert!(reaches(ROLES, "admin", "admin"));
+        assert!(reaches(ROLES, "admin", "user"));
+        assert!(reaches(ROLES, "admin", "updateProfile"));
+        assert!(!reaches(ROLES, "user", "admin"));
+        assert!(!reaches(ROLES, "user", "deleteUser"));
+        assert!(!reaches(ROLES, "updateProfile", "user"));
+    }
+
+    #[test]
+    fn shared_descendants_and_existing_cycles_terminate() {
+        let edges = &[("a", "b"), ("a", "c"), ("b", "d"), ("c", "d"), ("d", "b")];
+        assert!(reaches(edges, "a", "d"));
+        assert!(!reaches(edges, "a", "e"));
+        assert!(!reaches(edges, "b", "a"));
+    }
+}
@@ -0,0 +1,169 @@
+use crate::models::UserModel as User;
+use crate::schema::data_exports;
+use chrono::*;
+use cuid::cuid;
+use diesel::mysql::MysqlConnection;
+use diesel::prelude::*;
+use diesel::result::Error;
+
+/// An export that is pending for longer was interrupted, for instance by a restart
+pub const PENDING_TIMEOUT_IN_MINUTES: i64 = 30;
+
+fn pending_cutoff() -> NaiveDateTime {
+    Utc::now().naive_utc() - Duration::minutes(PENDING_TIMEOUT_IN_MINUTES)
+}
+
+/// The steps of a personal data export
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum DataExportStatus {
+    Pending,
+    Ready,
+    Failed,
+    Expired,
+}
+
+impl DataExportStatus {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            DataExportStatus::Pending => "pending",
+            DataExportStatus::Ready => "ready",
+            DataExportStatus::Failed => "failed",
+            DataExportStatus::Expired => "expired",
+        }
+    }
+
+    fn from_str(value: &str) -> Self {
+        match value {
+            "ready" => DataExportStatus::Ready,
+            "failed" => DataExportStatus::Failed,
+            "expired" => DataExportStatus::Expired,
+            _ => DataExportStatus::Pending,
+        }
+    }
+}
+
+#[derive(Identifiable, Associations, Queryable, Clone, Debug)]
+#[belongs_to(User)]
+#[table_name = "data_exports"]
+pub struct DataExportModel {
+    pub id: String,
+    pub user_id: String,
+    pub requested_by: String,
+    pub status: String,
+    pub created_at: NaiveDateTime,
+    pub completed_at: Option<NaiveDateTime>,
+    pub expires_at: Option<NaiveDateTime>,
+}
+
+impl DataExportModel {
+    pub fn find(export_id: &str, conn: &MysqlConnection) -> Result<Option<DataExportModel>, Error> {
+        use crate::schema::data_exports::dsl::*;
+        data_exports.filter(id.eq(export_id)).first(conn).optional()
+    }
+
+    /// Finds an export of a User that is still being built for the same requester, the interrupted
+    /// ones are ignored
+    pub fn find_pending(
+        user: &str,
+        requester: &str,
+        conn: &MysqlConnection,
+    ) -> Result<Option<DataExportModel>, Error> {
+        use crate::schema::data_exports::dsl::*;
+        data_exports
+            .filter(user_id.eq(user))
+            .filter(requested_by.eq(requester))
+            .filter(status.eq(DataExportStatus::Pending.as_str()))
+            .filter(created_at.gt(pending_cutoff()))
+            .first(conn)
+            .optional()
+    }
+
+    /// Marks the exports that are pending since before [`PENDING_TIMEOUT_IN_MINUTES`] as failed
+    pub fn fail_interrupted(conn: &MysqlConnection) -> Result<usize, Error> {
+        use crate::schema::data_exports::dsl::*;
+        diesel::update(
+            data_exports
+                .filter(status.eq(DataExportStatus::Pending.as_str()))
+                .filter(created_at.le(pending_cutoff())),
+        )
+        .set(status.eq(DataExportStatus::Failed.as_str()))
+        .execute(conn)
+    }
+
+    /// Ready exports whose download expired, their archives can be removed
+    pub fn find_expired(conn: &MysqlConnection) -> Result<Vec<DataExportModel>, Error> {
+        use crate::schema::data_exports::dsl::*;
+        data_exports
+            .filter(status.eq(DataExportStatus::Ready.as_str()))
+            .filter(expires_at.lt(Utc::now().naive_utc()))
+            .load(conn)
+    }
+
+    pub fn current_status(&self) -> DataExportStatus {
+        match DataExportStatus::from_str(&self.status) {
+            DataExportStatus::Ready if !self.is_downloadable() => DataExportStatus::Expired,
+            export_status => export_status,
+        }
+    }
+
+    /// If the archive is built and its download did not expire
+    pub fn is_downloadable(&self) -> bool {
+        DataExportStatus::from_str(&self.status) == DataExportStatus::Ready
+            && self
+                .expires_at
+                .map_or(false, |date| date > Utc::now().naive_utc())
+    }
+
+    /// Marks the archive as built, it can be downloaded until `download_expires_at`
+    pub fn mark_ready(
+        &self,
+        download_expires_at: NaiveDateTime,
+        conn: &MysqlConnection,
+    ) -> Result<usize, Error> {
+        use crate::schema::data_exports::dsl::*;
+        diesel::update(self)
+            .set((
+                status.eq(DataExportStatus::Ready.as_str()),
+                completed_at.eq(Utc::now().naive_utc()),
+                expires_at.eq(download_expires_at),
+            ))
+            .execute(conn)
+    }
+
+    pub fn mark(
+        &self,
+        new_status: DataExportStatus,
+        conn: &MysqlConnection,
+    ) -> Result<usize, Error> {
+        use crate::schema::data_exports::dsl::*;
+        diesel::update(self)
+            .set(status.eq(new_status.as_str()))
+            .execute(conn)
+    }
+}
+
+#[derive(Insertable)]
+#[table_name = "data_exports"]
+pub struct NewDataExportModel<'a> {
+    pub id: String,
+    pub user_id: &'a str,
+    pub requested_by: &'a str,
+}
+
+impl<'a> NewDataExportModel<'a> {
+    pub fn new(user_id: &'a str, requested_by: &'a str) -> Self {
+        Self {
+            id: cuid().unwrap(),
+            user_id,
+            requested_by,
+        }
+    }
+
+    pub fn save(self, conn: &MysqlConnection) -> Result<DataExportModel, Error> {
+        use crate::schema::data_exports::dsl::*;
+        diesel::insert_into(data_exports)
+            .values(&self)
+            .execute(conn)?;
+        data_exports.filter(id.eq(self.id)).first(conn)
+    }
+}
@@ -1,5 +1,5 @@
 use crate::models::UserModel as User;
-use crate::schema::external_user_providers;
+use crate::schema::{external_user_providers, users};
 use chrono::{NaiveDateTime, Utc};
 use diesel::mysql::MysqlConnection;
 use diesel::prelude::*;
@@ -19,6 +19,55 @@ pub struct ExternalUserProviderModel {
     pub deleted: bool,
 }
 
+impl ExternalUserProviderModel {
+    /// Finds an active External Provider account together with its linked User
+    pub fn find_with_user(
+        user_provider: UserProvider,
+        provider_external_id: &str,
+        conn: &MysqlConnection,
+    ) -> Result<Option<(ExternalUserProviderModel, User)>, Error> {
+        use crate::schema::external_user_providers::dsl::*;
+        external_user_providers
+            .inner_join(users::table.on(users::id.eq(user_id)))
+            .filter(provider.eq(user_provider))
+            .filter(external_id.eq(provider_external_id))
+            .filter(deleted.eq(false))
+            .filter(users::deleted.eq(false))
+            .first::<(ExternalUserProviderModel, User)>(conn)
+            .optional()
+    }
+
+    /// Counts the active External Provider accounts linked to a User
+    pub fn count_by_user(user: &User, conn: &MysqlConnection) -> Result<i64, Error> {
+        use crate::schema::external_user_providers::dsl::*;
+        external_user_providers
+            .filter(user_id.eq(&user.id))
+            .filter(deleted.eq(false))
+            .count()
+            .get_result(conn)
+    }
+
+    /// Soft deletes this link using the `deleted` flag
+    pub fn soft_delete(&self, conn: &MysqlConnection) -> Result<usize, Error> {
+        use crate::schema::external_user_providers::dsl::*;
+        diesel::update(external_user_providers.filter(id.eq(&self.id)))
+            .set(deleted.eq(true))
+            .execute(conn)
+    }
+
+    pub fn update_email(
+        &self,
+        new_email: &str,
+        conn: &MysqlConnection,
+    ) -> Result<ExternalUserProviderModel, Error> {
+        use crate::schema::external_user_providers::dsl::*;
+        diesel::update(external_user_providers.filter(id.eq(&self.id)))
+            .set(email.eq(new_email))
+            .execute(conn)?;
+        external_user_providers.filter(id.eq(&self.id)).first(conn)
+    }
+}
+
 #[DieselType = "UserProviderMapping"]
 #[derive(Debug, PartialEq, DbEnum, Deserialize, Copy, Clone)]
 pub enum UserProvider {
@@ -45,7 +94,7 @@ impl<'a> NewExternalUserProviderModel<'a> {
         user_id: String,
         external_id: String,
         provider: UserProvider,
-        email: &'a String,
+        email: Option<&'a String>,
     ) -> Self {
         let now = Utc::now().naive_utc();
         Self {
@@ -53,7 +102,7 @@ impl<'a> NewExternalUserProviderModel<'a> {
             user_id,
             external_id,
             provider,
-            email: Some(email),
+            email,
             created_at: Some(now.clone()),
             updated_at: Some(now),
             deleted: Some(false),
@@ -6,4 +6,18 @@ pub mod user_token;
 pub use user_token::*;
 pub mod external_user_provider;
 pub use external_user_provider::*;
+pub mod password_reset_token;
+pub use password_reset_token::*;
+pub mod user_mfa;
+pub use user_mfa::*;
+pub mod user_profile;
+pub use user_profile::*;
+pub mod audit_event;
+pub use audit_event::*;
+pub mod data_export;
+pub use data_export::*;
+pub mod oauth_client;
+pub use oauth_client::*;
+pub mod personal_access_token;
+pub use personal_access_token::*;
 pub mod utils;
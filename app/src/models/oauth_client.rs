@@ -0,0 +1,181 @@
+use crate::schema::oauth_clients;
+use crate::utils::argon::constant_time_eq;
+use crate::utils::token::hash_token;
+use chrono::*;
+use cuid::cuid;
+use diesel::mysql::MysqlConnection;
+use diesel::prelude::*;
+use diesel::result::Error;
+
+/// What a token that doesn't belong to a User session is allowed to do
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Scope {
+    UsersRead,
+    UsersWrite,
+    ProfileRead,
+    ProfileWrite,
+}
+
+impl Scope {
+    pub const ALL: [Scope; 4] = [
+        Scope::UsersRead,
+        Scope::UsersWrite,
+        Scope::ProfileRead,
+        Scope::ProfileWrite,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Scope::UsersRead => "users:read",
+            Scope::UsersWrite => "users:write",
+            Scope::ProfileRead => "profile:read",
+            Scope::ProfileWrite => "profile:write",
+        }
+    }
+
+    pub fn from_name(value: &str) -> Option<Scope> {
+        Self::ALL
+            .iter()
+            .copied()
+            .find(|scope| scope.as_str() == value)
+    }
+
+    /// Parses a space separated list of scopes as used by OAuth2, unknown scopes are an error
+    pub fn parse_list(value: &str) -> Option<Vec<Scope>> {
+        value.split_whitespace().map(Scope::from_name).collect()
+    }
+
+    pub fn join(scopes: &[Scope]) -> String {
+        scopes
+            .iter()
+            .map(|scope| scope.as_str())
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
+
+#[derive(Identifiable, Queryable, Clone, Debug)]
+#[table_name = "oauth_clients"]
+pub struct OAuthClientModel {
+    pub id: String,
+    pub name: String,
+    pub secret_hash: String,
+    pub scopes: String,
+    pub created_by: Option<String>,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+    pub revoked_at: Option<NaiveDateTime>,
+}
+
+impl OAuthClientModel {
+    pub fn find(
+        client_id: &str,
+        conn: &MysqlConnection,
+    ) -> Result<Option<OAuthClientModel>, Error> {
+        use crate::schema::oauth_clients::dsl::*;
+        oauth_clients
+            .filter(id.eq(client_id))
+            .first(conn)
+            .optional()
+    }
+
+    /// Finds a client that was not revoked
+    pub fn find_active(
+        client_id: &str,
+        conn: &MysqlConnection,
+    ) -> Result<Option<OAuthClientModel>, Error> {
+        use crate::schema::oauth_clients::dsl::*;
+        oauth_clients
+            .filter(id.eq(client_id))
+            .filter(revoked_at.is_null())
+            .first(conn)
+            .optional()
+    }
+
+    pub fn all(conn: &MysqlConnection) -> Result<Vec<OAuthClientModel>, Error> {
+        use crate::schema::oauth_clients::dsl::*;
+        oauth_clients.order(created_at.desc()).load(conn)
+    }
+
+    /// The secrets are random, so their SHA-256 digest is enough and keeps the token route fast
+    pub fn check_secret(&self, secret: &str) -> bool {
+        constant_time_eq(hash_token(secret).as_bytes(), self.secret_hash.as_bytes())
+    }
+
+    pub fn allowed_scopes(&self) -> Vec<Scope> {
+        self.scopes
+            .split_whitespace()
+            .filter_map(Scope::from_name)
+            .collect()
+    }
+
+    pub fn revoke(&self, conn: &MysqlConnection) -> Result<usize, Error> {
+        use crate::schema::oauth_clients::dsl::*;
+        diesel::update(self)
+            .set(revoked_at.eq(Utc::now().naive_utc()))
+            .execute(conn)
+    }
+}
+
+#[derive(Insertable)]
+#[table_name = "oauth_clients"]
+pub struct NewOAuthClientModel<'a> {
+    pub id: String,
+    pub name: &'a str,
+    pub secret_hash: String,
+    pub scopes: String,
+    pub created_by: Option<&'a str>,
+}
+
+impl<'a> NewOAuthClientModel<'a> {
+    /// A new client, the secret is only known by the caller
+    pub fn new(name: &'a str, secret: &str, scopes: &[Scope], created_by: Option<&'a str>) -> Self {
+        Self {
+            id: cuid().unwrap(),
+            name,
+            secret_hash: hash_token(secret),
+            scopes: Scope::join(scopes),
+            created_by,
+        }
+    }
+
+    pub fn save(self, conn: &MysqlConnection) -> Result<OAuthClientModel, Error> {
+        use crate::schema::oauth_clients::dsl::*;
+        diesel::insert_into(oauth_clients)
+            .values(&self)
+            .execute(conn)?;
+        oauth_clients.filter(id.eq(self.id)).first(conn)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_list_reads_space_separated_scopes() {
+        assert_eq!(
+            Scope::parse_list("users:read  profile:write\tusers:write"),
+            Some(vec![
+                Scope::UsersRead,
+                Scope::ProfileWrite,
+                Scope::UsersWrite
+            ])
+        );
+        assert_eq!(Scope::parse_list(""), Some(vec![]));
+    }
+
+    #[test]
+    fn parse_list_refuses_unknown_scopes() {
+        assert_eq!(Scope::parse_list("users:read admin"), None);
+        assert_eq!(Scope::parse_list("users:read,profile:read"), None);
+        assert_eq!(Scope::parse_list("USERS:READ"), None);
+    }
+
+    #[test]
+    fn join_is_the_inverse_of_parse_list() {
+        let joined = Scope::join(&Scope::ALL);
+        assert_eq!(joined, "users:read users:write profile:read profile:write");
+        assert_eq!(Scope::parse_list(&joined), Some(Scope::ALL.to_vec()));
+    }
+}
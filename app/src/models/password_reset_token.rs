@@ -0,0 +1,78 @@
+use crate::models::UserModel as User;
+use crate::schema::password_reset_tokens;
+use chrono::*;
+use cuid::cuid;
+use diesel::mysql::MysqlConnection;
+use diesel::prelude::*;
+use diesel::result::Error;
+
+#[derive(Identifiable, Associations, Queryable, Clone, Debug)]
+#[belongs_to(User)]
+#[table_name = "password_reset_tokens"]
+pub struct PasswordResetTokenModel {
+    pub id: String,
+    pub user_id: String,
+    pub token_hash: String,
+    pub expires_at: NaiveDateTime,
+    pub used_at: Option<NaiveDateTime>,
+    pub created_at: NaiveDateTime,
+}
+
+const DURATION_OF_RESET_TOKEN_IN_MINUTES: i64 = 30;
+
+impl PasswordResetTokenModel {
+    /// Finds a reset token that was not used and is not expired by its hash
+    pub fn find_valid(
+        hash: &str,
+        conn: &MysqlConnection,
+    ) -> Result<Option<PasswordResetTokenModel>, Error> {
+        use crate::schema::password_reset_tokens::dsl::*;
+        password_reset_tokens
+            .filter(token_hash.eq(hash))
+            .filter(used_at.is_null())
+            .filter(expires_at.ge(Utc::now().naive_utc()))
+            .first(conn)
+            .optional()
+    }
+
+    /// Marks every pending reset token of a User as used, so each token works only once
+    pub fn use_all_from_user(user: &str, conn: &MysqlConnection) -> Result<usize, Error> {
+        use crate::schema::password_reset_tokens::dsl::*;
+        diesel::update(
+            password_reset_tokens
+                .filter(user_id.eq(user))
+                .filter(used_at.is_null()),
+        )
+        .set(used_at.eq(Utc::now().naive_utc()))
+        .execute(conn)
+    }
+}
+
+#[derive(Insertable)]
+#[table_name = "password_reset_tokens"]
+pub struct NewPasswordResetTokenModel<'a> {
+    pub id: String,
+    pub user_id: &'a str,
+    pub token_hash: String,
+    pub expires_at: NaiveDateTime,
+}
+
+impl<'a> NewPasswordResetTokenModel<'a> {
+    pub fn new(user_id: &'a str, token_hash: String) -> Self {
+        Self {
+            id: cuid().unwrap(),
+            user_id,
+            token_hash,
+            expires_at: Utc::now().naive_utc()
+                + Duration::minutes(DURATION_OF_RESET_TOKEN_IN_MINUTES),
+        }
+    }
+
+    pub fn save(self, conn: &MysqlConnection) -> Result<PasswordResetTokenModel, Error> {
+        use crate::schema::password_reset_tokens::dsl::*;
+        diesel::insert_into(password_reset_tokens)
+            .values(&self)
+            .execute(conn)?;
+        password_reset_tokens.filter(id.eq(self.id)).first(conn)
+    }
+}
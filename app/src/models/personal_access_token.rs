@@ -0,0 +1,142 @@
+use crate::models::{Scope, UserModel as User};
+use crate::schema::personal_access_tokens;
+use crate::utils::token::hash_token;
+use chrono::*;
+use cuid::cuid;
+use diesel::mysql::MysqlConnection;
+use diesel::prelude::*;
+use diesel::result::Error;
+
+/// Tells the personal access tokens apart from the JWTs in the Authorization header
+pub const PERSONAL_ACCESS_TOKEN_PREFIX: &str = "pat_";
+
+// The last use is stored at most once per interval instead of on every request
+const LAST_USED_INTERVAL_IN_MINUTES: i64 = 5;
+
+#[derive(Identifiable, Associations, Queryable, Clone, Debug)]
+#[belongs_to(User)]
+#[table_name = "personal_access_tokens"]
+pub struct PersonalAccessTokenModel {
+    pub id: String,
+    pub user_id: String,
+    pub name: String,
+    pub token_hash: String,
+    pub scopes: String,
+    pub expires_at: Option<NaiveDateTime>,
+    pub last_used_at: Option<NaiveDateTime>,
+    pub created_at: NaiveDateTime,
+    pub revoked_at: Option<NaiveDateTime>,
+}
+
+impl PersonalAccessTokenModel {
+    /// Finds the token that was not revoked nor expired by its secret
+    pub fn find_active(
+        token: &str,
+        conn: &MysqlConnection,
+    ) -> Result<Option<PersonalAccessTokenModel>, Error> {
+        use crate::schema::personal_access_tokens::dsl::*;
+        personal_access_tokens
+            .filter(token_hash.eq(hash_token(token)))
+            .filter(revoked_at.is_null())
+            .filter(
+                expires_at
+                    .is_null()
+                    .or(expires_at.gt(Utc::now().naive_utc())),
+            )
+            .first(conn)
+            .optional()
+    }
+
+    /// The tokens of a User that were not revoked, the expired ones included
+    pub fn find_by_user(
+        user: &str,
+        conn: &MysqlConnection,
+    ) -> Result<Vec<PersonalAccessTokenModel>, Error> {
+        use crate::schema::personal_access_tokens::dsl::*;
+        personal_access_tokens
+            .filter(user_id.eq(user))
+            .filter(revoked_at.is_null())
+            .order(created_at.desc())
+            .load(conn)
+    }
+
+    pub fn find_for_user(
+        token_id: &str,
+        user: &str,
+        conn: &MysqlConnection,
+    ) -> Result<Option<PersonalAccessTokenModel>, Error> {
+        use crate::schema::personal_access_tokens::dsl::*;
+        personal_access_tokens
+            .filter(id.eq(token_id))
+            .filter(user_id.eq(user))
+            .filter(revoked_at.is_null())
+            .first(conn)
+            .optional()
+    }
+
+    pub fn allowed_scopes(&self) -> Vec<Scope> {
+        self.scopes
+            .split_whitespace()
+            .filter_map(Scope::from_name)
+            .collect()
+    }
+
+    /// Stores that the token was used, skipped when it was already stored recently
+    pub fn touch(&self, conn: &MysqlConnection) -> Result<usize, Error> {
+        use crate::schema::personal_access_tokens::dsl::*;
+        let now = Utc::now().naive_utc();
+        let recently_used = self.last_used_at.map_or(false, |date| {
+            date > now - Duration::minutes(LAST_USED_INTERVAL_IN_MINUTES)
+        });
+        if recently_used {
+            return Ok(0);
+        }
+        diesel::update(self).set(last_used_at.eq(now)).execute(conn)
+    }
+
+    pub fn revoke(&self, conn: &MysqlConnection) -> Result<usize, Error> {
+        use crate::schema::personal_access_tokens::dsl::*;
+        diesel::update(self)
+            .set(revoked_at.eq(Utc::now().naive_utc()))
+            .execute(conn)
+    }
+}
+
+#[derive(Insertable)]
+#[table_name = "personal_access_tokens"]
+pub struct NewPersonalAccessTokenModel<'a> {
+    pub id: String,
+    pub user_id: &'a str,
+    pub name: &'a str,
+    pub token_hash: String,
+    pub scopes: String,
+    pub expires_at: Option<NaiveDateTime>,
+}
+
+impl<'a> NewPersonalAccessTokenModel<'a> {
+    /// A new token, the secret is only known by the caller
+    pub fn new(
+        user_id: &'a str,
+        name: &'a str,
+        token: &str,
+        scopes: &[Scope],
+        expires_at: Option<NaiveDateTime>,
+    ) -> Self {
+        Self {
+            id: cuid().unwrap(),
+            user_id,
+            name,
+            token_hash: hash_token(token),
+            scopes: Scope::join(scopes),
+            expires_at,
+        }
+    }
+
+    pub fn save(self, conn: &MysqlConnection) -> Result<PersonalAccessTokenModel, Error> {
+        use crate::schema::personal_access_tokens::dsl::*;
+        diesel::insert_into(personal_access_tokens)
+            .values(&self)
+            .execute(conn)?;
+        personal_access_tokens.filter(id.eq(self.id)).first(conn)
+    }
+}
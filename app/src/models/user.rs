@@ -1,6 +1,8 @@
-use crate::models::{AuthAssignmentModel, UserTokenModel};
+use crate::models::AuthAssignmentModel;
 use crate::schema::users;
-use crate::utils::argon::{make_hash, make_salt};
+use crate::utils::argon::{
+    hash_password, needs_rehash, verify_dummy_password, verify_legacy_password, verify_password,
+};
 use chrono::{NaiveDateTime, Utc};
 use cuid::cuid;
 use diesel::mysql::MysqlConnection;
@@ -11,12 +13,15 @@ use diesel::result::Error;
 #[table_name = "users"]
 pub struct UserModel {
     pub id: String,
-    pub hash: Vec<u8>,
-    pub salt: String,
+    // Raw Argon2i hash and salt of the passwords set before the PHC strings
+    pub hash: Option<Vec<u8>>,
+    pub salt: Option<String>,
     pub email: String,
     pub created_at: NaiveDateTime,
     pub updated_at: NaiveDateTime,
     pub deleted: bool,
+    pub email_verified_at: Option<NaiveDateTime>,
+    pub password_hash: Option<String>,
 }
 
 impl UserModel {
@@ -29,27 +34,98 @@ impl UserModel {
             .filter(user_id.eq(self.id.to_string()))
             .load(conn)
     }
-    pub fn find_user(user_info: &SlimUser, conn: &MysqlConnection) -> Option<UserModel> {
-        use crate::schema::user_tokens::dsl::{token, user_id, user_tokens};
-        use crate::schema::users::dsl::{id, users};
-        match users
-            .inner_join(user_tokens.on(user_id.eq(id)))
-            .filter(id.eq(user_info.id.as_ref()?))
-            .filter(token.eq(user_info.token.as_ref()?))
-            .first::<(UserModel, UserTokenModel)>(conn)
-        {
-            Ok((user, ..)) => Some(user),
-            Err(_) => None,
+    /// Users created by an External Provider don't have a local password
+    pub fn has_password(&self) -> bool {
+        self.password_hash.is_some() || self.hash.is_some()
+    }
+    /// Verifies the password against the PHC string, or the legacy hash when it was not migrated yet
+    pub fn check_password(&self, password: &str) -> bool {
+        match (&self.password_hash, &self.hash, &self.salt) {
+            (Some(encoded), _, _) => verify_password(password, encoded),
+            (None, Some(hash), Some(salt)) => verify_legacy_password(password, salt, hash),
+            _ => {
+                verify_dummy_password(password);
+                false
+            }
         }
     }
+    /// If the password is stored as a legacy hash or with outdated Argon2 parameters
+    pub fn password_needs_rehash(&self) -> bool {
+        self.password_hash
+            .as_ref()
+            .map_or(true, |encoded| needs_rehash(encoded))
+    }
+    /// Stores the password again with the current parameters, removing the legacy hash
+    pub fn rehash_password(
+        &self,
+        password: &str,
+        conn: &MysqlConnection,
+    ) -> Result<UserModel, Error> {
+        UpdatedUserModel::new(None, Some(password.to_string())).update(self, conn)
+    }
+    pub fn mark_email_verified(&self, conn: &MysqlConnection) -> Result<UserModel, Error> {
+        use crate::schema::users::dsl::*;
+        diesel::update(users.filter(id.eq(&self.id)))
+            .set(email_verified_at.eq(Utc::now().naive_utc()))
+            .execute(conn)?;
+        users.filter(id.eq(&self.id)).first(conn)
+    }
+    /// Finds a User that was not deleted by its E-mail
+    pub fn find_by_email(
+        user_email: &str,
+        conn: &MysqlConnection,
+    ) -> Result<Option<UserModel>, Error> {
+        use crate::schema::users::dsl::*;
+        users
+            .filter(email.eq(user_email))
+            .filter(deleted.eq(false))
+            .first(conn)
+            .optional()
+    }
+    /// Finds a User by its E-mail even when it was deleted, the E-mail stays reserved
+    pub fn find_by_email_with_deleted(
+        user_email: &str,
+        conn: &MysqlConnection,
+    ) -> Result<Option<UserModel>, Error> {
+        use crate::schema::users::dsl::*;
+        users.filter(email.eq(user_email)).first(conn).optional()
+    }
+    /// Finds a User that was not deleted by its id
+    pub fn find(user_id: &str, conn: &MysqlConnection) -> Result<Option<UserModel>, Error> {
+        use crate::schema::users::dsl::*;
+        users
+            .filter(id.eq(user_id))
+            .filter(deleted.eq(false))
+            .first(conn)
+            .optional()
+    }
+    /// Finds a User by its id even when it was deleted, only for admins
+    pub fn find_with_deleted(
+        user_id: &str,
+        conn: &MysqlConnection,
+    ) -> Result<Option<UserModel>, Error> {
+        use crate::schema::users::dsl::*;
+        users.filter(id.eq(user_id)).first(conn).optional()
+    }
+    /// Marks the User as deleted or restores it, its data is kept
+    pub fn set_deleted(
+        &self,
+        is_deleted: bool,
+        conn: &MysqlConnection,
+    ) -> Result<UserModel, Error> {
+        use crate::schema::users::dsl::*;
+        diesel::update(users.filter(id.eq(&self.id)))
+            .set(deleted.eq(is_deleted))
+            .execute(conn)?;
+        users.filter(id.eq(&self.id)).first(conn)
+    }
 }
 
 #[derive(Insertable)]
 #[table_name = "users"]
 pub struct NewUser<'a> {
     pub id: Option<String>,
-    pub hash: Option<Vec<u8>>,
-    pub salt: Option<String>,
+    pub password_hash: Option<String>,
     pub email: Option<&'a String>,
     pub created_at: Option<NaiveDateTime>,
     pub updated_at: Option<NaiveDateTime>,
@@ -61,8 +137,7 @@ impl<'a> Default for NewUser<'a> {
         let now = Utc::now().naive_utc();
         Self {
             id: Some(cuid().unwrap()),
-            hash: None,
-            salt: None,
+            password_hash: None,
             email: None,
             created_at: Some(now.clone()),
             updated_at: Some(now),
@@ -72,6 +147,7 @@ impl<'a> Default for NewUser<'a> {
 }
 
 impl<'a> NewUser<'a> {
+    /// A User without a local password
     pub fn new_without_pw(email: &'a String) -> NewUser<'a> {
         NewUser {
             email: Some(email),
@@ -80,11 +156,8 @@ impl<'a> NewUser<'a> {
     }
 
     pub fn new(email: &'a String, password: &'a String) -> NewUser<'a> {
-        let salt = make_salt();
-        let hash = make_hash(password, &salt);
         NewUser {
-            salt: Some(salt),
-            hash: Some(hash),
+            password_hash: Some(hash_password(password)),
             ..Self::new_without_pw(email)
         }
     }
@@ -96,49 +169,70 @@ impl<'a> NewUser<'a> {
     }
 }
 
-#[derive(Insertable, AsChangeset)]
+#[derive(AsChangeset)]
 #[table_name = "users"]
 pub struct UpdatedUserModel {
     pub email: Option<String>,
-    pub hash: Option<Vec<u8>>,
-    pub salt: Option<String>,
+    pub password_hash: Option<String>,
+    // `Some(None)` clears the legacy hash and salt when the password changes
+    pub hash: Option<Option<Vec<u8>>>,
+    pub salt: Option<Option<String>>,
 }
 
 impl UpdatedUserModel {
     pub fn new(email: Option<String>, password: Option<String>) -> UpdatedUserModel {
-        let (salt, hash) = {
-            if let Some(password) = password {
-                let salt = make_salt();
-                let hash = make_hash(&password, &salt);
-                (Some(salt), Some(hash))
-            } else {
-                (None, None)
-            }
+        let password_hash = password.map(|password| hash_password(&password));
+        let (hash, salt) = match password_hash {
+            Some(_) => (Some(None), Some(None)),
+            None => (None, None),
         };
-        Self { email, hash, salt }
+        Self {
+            email,
+            password_hash,
+            hash,
+            salt,
+        }
     }
 
+    /// Updates the User, a changed E-mail has to be verified again
     pub fn update(&self, user: &UserModel, conn: &MysqlConnection) -> Result<UserModel, Error> {
         use crate::schema::users::dsl::*;
         diesel::update(users)
             .filter(id.eq(&user.id))
             .set(self)
             .execute(conn)?;
+        if self.email_changed(user) {
+            diesel::update(users)
+                .filter(id.eq(&user.id))
+                .set(email_verified_at.eq(None::<NaiveDateTime>))
+                .execute(conn)?;
+        }
         users.filter(id.eq(&user.id)).first(conn)
     }
+
+    pub fn email_changed(&self, user: &UserModel) -> bool {
+        self.email
+            .as_ref()
+            .map_or(false, |new_email| new_email != &user.email)
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, Default)]
 pub struct SlimUser {
     pub id: Option<String>,
+    pub session_id: Option<String>,
     pub token: Option<String>,
+    /// The admin impersonating the User
+    pub actor_id: Option<String>,
 }
 
 impl From<UserModel> for SlimUser {
     fn from(user: UserModel) -> Self {
         SlimUser {
             id: Some(user.id),
+            session_id: None,
             token: None,
+            actor_id: None,
         }
     }
 }
@@ -0,0 +1,145 @@
+use crate::models::UserModel as User;
+use crate::schema::{user_mfa, user_mfa_recovery_codes};
+use chrono::*;
+use cuid::cuid;
+use diesel::mysql::MysqlConnection;
+use diesel::prelude::*;
+use diesel::result::Error;
+
+#[derive(Identifiable, Associations, Queryable, Clone, Debug)]
+#[belongs_to(User)]
+#[primary_key(user_id)]
+#[table_name = "user_mfa"]
+pub struct UserMfaModel {
+    pub user_id: String,
+    pub secret_encrypted: Vec<u8>,
+    pub last_used_step: Option<i64>,
+    pub enabled_at: Option<NaiveDateTime>,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+}
+
+impl UserMfaModel {
+    pub fn find(user: &str, conn: &MysqlConnection) -> Result<Option<UserMfaModel>, Error> {
+        use crate::schema::user_mfa::dsl::*;
+        user_mfa.filter(user_id.eq(user)).first(conn).optional()
+    }
+
+    /// Finds the Two-Factor Authentication of a User only when its enrollment was confirmed
+    pub fn find_enabled(user: &str, conn: &MysqlConnection) -> Result<Option<UserMfaModel>, Error> {
+        use crate::schema::user_mfa::dsl::*;
+        user_mfa
+            .filter(user_id.eq(user))
+            .filter(enabled_at.is_not_null())
+            .first(conn)
+            .optional()
+    }
+
+    pub fn enable(&self, step: i64, conn: &MysqlConnection) -> Result<usize, Error> {
+        use crate::schema::user_mfa::dsl::*;
+        diesel::update(self)
+            .set((
+                enabled_at.eq(Utc::now().naive_utc()),
+                last_used_step.eq(step),
+            ))
+            .execute(conn)
+    }
+
+    /// Stores the last accepted time step, so a code can't be used twice
+    pub fn use_step(&self, step: i64, conn: &MysqlConnection) -> Result<usize, Error> {
+        use crate::schema::user_mfa::dsl::*;
+        diesel::update(self)
+            .set(last_used_step.eq(step))
+            .execute(conn)
+    }
+
+    /// Disables the Two-Factor Authentication removing the secret and the recovery codes
+    pub fn delete(&self, conn: &MysqlConnection) -> Result<usize, Error> {
+        UserMfaRecoveryCodeModel::delete_from_user(&self.user_id, conn)?;
+        diesel::delete(self).execute(conn)
+    }
+}
+
+#[derive(Insertable)]
+#[table_name = "user_mfa"]
+pub struct NewUserMfaModel<'a> {
+    pub user_id: &'a str,
+    pub secret_encrypted: Vec<u8>,
+}
+
+impl<'a> NewUserMfaModel<'a> {
+    pub fn new(user_id: &'a str, secret_encrypted: Vec<u8>) -> Self {
+        Self {
+            user_id,
+            secret_encrypted,
+        }
+    }
+
+    /// Saves a pending enrollment replacing a previous one that was not confirmed
+    pub fn save(self, conn: &MysqlConnection) -> Result<UserMfaModel, Error> {
+        use crate::schema::user_mfa::dsl::*;
+        diesel::replace_into(user_mfa).values(&self).execute(conn)?;
+        user_mfa.filter(user_id.eq(self.user_id)).first(conn)
+    }
+}
+
+#[derive(Identifiable, Associations, Queryable, Clone, Debug)]
+#[belongs_to(User)]
+#[table_name = "user_mfa_recovery_codes"]
+pub struct UserMfaRecoveryCodeModel {
+    pub id: String,
+    pub user_id: String,
+    pub code_hash: String,
+    pub used_at: Option<NaiveDateTime>,
+    pub created_at: NaiveDateTime,
+}
+
+impl UserMfaRecoveryCodeModel {
+    /// Marks a recovery code as used, returns if there was an unused code with this hash
+    pub fn use_code(user: &str, hash: &str, conn: &MysqlConnection) -> Result<bool, Error> {
+        use crate::schema::user_mfa_recovery_codes::dsl::*;
+        let updated = diesel::update(
+            user_mfa_recovery_codes
+                .filter(user_id.eq(user))
+                .filter(code_hash.eq(hash))
+                .filter(used_at.is_null()),
+        )
+        .set(used_at.eq(Utc::now().naive_utc()))
+        .execute(conn)?;
+        Ok(updated > 0)
+    }
+
+    pub fn delete_from_user(user: &str, conn: &MysqlConnection) -> Result<usize, Error> {
+        use crate::schema::user_mfa_recovery_codes::dsl::*;
+        diesel::delete(user_mfa_recovery_codes.filter(user_id.eq(user))).execute(conn)
+    }
+
+    /// Replaces every recovery code of a User by the new hashes
+    pub fn replace_from_user(
+        user: &str,
+        hashes: Vec<String>,
+        conn: &MysqlConnection,
+    ) -> Result<usize, Error> {
+        use crate::schema::user_mfa_recovery_codes::dsl::*;
+        Self::delete_from_user(user, conn)?;
+        let new_codes: Vec<NewUserMfaRecoveryCodeModel> = hashes
+            .into_iter()
+            .map(|hash| NewUserMfaRecoveryCodeModel {
+                id: cuid().unwrap(),
+                user_id: user,
+                code_hash: hash,
+            })
+            .collect();
+        diesel::insert_into(user_mfa_recovery_codes)
+            .values(&new_codes)
+            .execute(conn)
+    }
+}
+
+#[derive(Insertable)]
+#[table_name = "user_mfa_recovery_codes"]
+pub struct NewUserMfaRecoveryCodeModel<'a> {
+    pub id: String,
+    pub user_id: &'a str,
+    pub code_hash: String,
+}
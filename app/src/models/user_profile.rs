@@ -0,0 +1,113 @@
+use crate::models::UserModel as User;
+use crate::schema::user_profiles;
+use chrono::*;
+use diesel::mysql::MysqlConnection;
+use diesel::prelude::*;
+use diesel::result::Error;
+
+#[derive(Identifiable, Associations, Queryable, Clone, Debug)]
+#[belongs_to(User)]
+#[primary_key(user_id)]
+#[table_name = "user_profiles"]
+pub struct UserProfileModel {
+    pub user_id: String,
+    pub display_name: Option<String>,
+    pub locale: Option<String>,
+    pub timezone: Option<String>,
+    pub avatar_url: Option<String>,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+    /// Prefix of the uploaded avatar files, it takes precedence over `avatar_url`
+    pub avatar_key: Option<String>,
+}
+
+impl UserProfileModel {
+    pub fn find(user: &str, conn: &MysqlConnection) -> Result<Option<UserProfileModel>, Error> {
+        use crate::schema::user_profiles::dsl::*;
+        user_profiles
+            .filter(user_id.eq(user))
+            .first(conn)
+            .optional()
+    }
+
+    /// The locale of the E-mails sent to a User
+    pub fn locale_of(user: &str, conn: &MysqlConnection) -> Result<Option<String>, Error> {
+        Ok(Self::find(user, conn)?.and_then(|profile| profile.locale))
+    }
+}
+
+#[derive(Insertable)]
+#[table_name = "user_profiles"]
+struct NewUserProfileModel<'a> {
+    user_id: &'a str,
+    display_name: Option<String>,
+    locale: Option<String>,
+    timezone: Option<String>,
+    avatar_url: Option<String>,
+    avatar_key: Option<String>,
+}
+
+/// Changes of a profile, `None` keeps a field and `Some(None)` clears it
+#[derive(AsChangeset, Default, Clone, Debug)]
+#[table_name = "user_profiles"]
+pub struct UpdatedUserProfileModel {
+    pub display_name: Option<Option<String>>,
+    pub locale: Option<Option<String>>,
+    pub timezone: Option<Option<String>>,
+    pub avatar_url: Option<Option<String>>,
+    pub avatar_key: Option<Option<String>>,
+}
+
+impl UpdatedUserProfileModel {
+    fn is_empty(&self) -> bool {
+        self.display_name.is_none()
+            && self.locale.is_none()
+            && self.timezone.is_none()
+            && self.avatar_url.is_none()
+            && self.avatar_key.is_none()
+    }
+
+    /// Keeps only the changes of the fields that `profile` doesn't have yet
+    pub fn missing_from(self, profile: &Option<UserProfileModel>) -> Self {
+        let profile = match profile {
+            Some(profile) => profile,
+            None => return self,
+        };
+        Self {
+            display_name: self.display_name.filter(|_| profile.display_name.is_none()),
+            locale: self.locale.filter(|_| profile.locale.is_none()),
+            timezone: self.timezone.filter(|_| profile.timezone.is_none()),
+            avatar_url: self.avatar_url.filter(|_| profile.avatar_url.is_none()),
+            avatar_key: self.avatar_key.filter(|_| profile.avatar_key.is_none()),
+        }
+    }
+
+    /// Updates the profile of a User, creating it when the User has none
+    pub fn save(
+        self,
+        user: &str,
+        conn: &MysqlConnection,
+    ) -> Result<Option<UserProfileModel>, Error> {
+        use crate::schema::user_profiles::dsl::*;
+        if self.is_empty() {
+            return UserProfileModel::find(user, conn);
+        }
+        if UserProfileModel::find(user, conn)?.is_some() {
+            diesel::update(user_profiles.filter(user_id.eq(user)))
+                .set(&self)
+                .execute(conn)?;
+        } else {
+            diesel::insert_into(user_profiles)
+                .values(&NewUserProfileModel {
+                    user_id: user,
+                    display_name: self.display_name.flatten(),
+                    locale: self.locale.flatten(),
+                    timezone: self.timezone.flatten(),
+                    avatar_url: self.avatar_url.flatten(),
+                    avatar_key: self.avatar_key.flatten(),
+                })
+                .execute(conn)?;
+        }
+        UserProfileModel::find(user, conn)
+    }
+}
@@ -1,4 +1,4 @@
-use crate::schema::user_tokens;
+use crate::schema::{used_refresh_tokens, user_tokens};
 use chrono::*;
 
 use crate::models::UserModel as User;
@@ -12,52 +12,139 @@ use diesel::result::Error;
 #[table_name = "user_tokens"]
 pub struct UserTokenModel {
     pub id: String,
-    pub token: String,
-    pub refresh_token: String,
     pub user_id: String,
     pub refresh_expire_at: NaiveDateTime,
     pub created_at: NaiveDateTime,
     pub updated_at: NaiveDateTime,
+    pub user_agent: Option<String>,
+    pub ip_address: Option<String>,
+    pub last_refreshed_at: Option<NaiveDateTime>,
+    pub family_id: String,
+    pub token_hash: String,
+    pub refresh_token_hash: String,
+}
+
+impl UserTokenModel {
+    /// Replaces the token hashes of this session keeping the information captured at login
+    pub fn rotate(
+        &self,
+        new_token_hash: &str,
+        new_refresh_token_hash: &str,
+        conn: &MysqlConnection,
+    ) -> Result<UserTokenModel, Error> {
+        use crate::schema::user_tokens::dsl::*;
+        let now = Utc::now().naive_local();
+        diesel::update(self)
+            .set((
+                token_hash.eq(new_token_hash),
+                refresh_token_hash.eq(new_refresh_token_hash),
+                refresh_expire_at.eq(refresh_expiration(now)),
+                last_refreshed_at.eq(now),
+            ))
+            .execute(conn)?;
+        user_tokens.filter(id.eq(&self.id)).first(conn)
+    }
+
+    /// Revokes every session created from the same login, returns the ids of the revoked sessions
+    pub fn revoke_family(family: &str, conn: &MysqlConnection) -> Result<Vec<String>, Error> {
+        use crate::schema::user_tokens::dsl::*;
+        let ids = user_tokens
+            .filter(family_id.eq(family))
+            .select(id)
+            .load::<String>(conn)?;
+        diesel::delete(user_tokens.filter(id.eq_any(&ids))).execute(conn)?;
+        Ok(ids)
+    }
+
+    /// Revokes every session of a User, returns the ids of the revoked sessions
+    pub fn revoke_all(user: &str, conn: &MysqlConnection) -> Result<Vec<String>, Error> {
+        use crate::schema::user_tokens::dsl::*;
+        let ids = user_tokens
+            .filter(user_id.eq(user))
+            .select(id)
+            .load::<String>(conn)?;
+        diesel::delete(user_tokens.filter(id.eq_any(&ids))).execute(conn)?;
+        Ok(ids)
+    }
+
+    /// Revokes a session of a User, returns if it existed
+    pub fn revoke(user: &str, session: &str, conn: &MysqlConnection) -> Result<bool, Error> {
+        use crate::schema::user_tokens::dsl::*;
+        let deleted = diesel::delete(user_tokens.filter(user_id.eq(user)).filter(id.eq(session)))
+            .execute(conn)?;
+        Ok(deleted > 0)
+    }
+
+    /// Revokes every session of a User except `current_session`, returns the ids of the revoked sessions
+    pub fn revoke_others(
+        user: &str,
+        current_session: &str,
+        conn: &MysqlConnection,
+    ) -> Result<Vec<String>, Error> {
+        use crate::schema::user_tokens::dsl::*;
+        let ids = user_tokens
+            .filter(user_id.eq(user))
+            .filter(id.ne(current_session))
+            .select(id)
+            .load::<String>(conn)?;
+        diesel::delete(user_tokens.filter(id.eq_any(&ids))).execute(conn)?;
+        Ok(ids)
+    }
 }
 
 #[derive(Insertable, Identifiable, AsChangeset)]
 #[table_name = "user_tokens"]
 pub struct NewUserTokenModel<'a> {
     pub id: String,
-    pub token: Option<&'a String>,
-    pub refresh_token: Option<&'a String>,
+    pub token_hash: Option<&'a String>,
+    pub refresh_token_hash: Option<&'a String>,
     pub user_id: Option<&'a String>,
     pub refresh_expire_at: Option<NaiveDateTime>,
+    pub user_agent: Option<&'a String>,
+    pub ip_address: Option<&'a String>,
+    pub family_id: Option<String>,
 }
 
 const DURATION_OF_REFRESH_TOKEN_IN_MINUTES: i64 = 525600;
 
+fn refresh_expiration(from: NaiveDateTime) -> NaiveDateTime {
+    from + Duration::minutes(DURATION_OF_REFRESH_TOKEN_IN_MINUTES)
+}
+
 impl<'a> Default for NewUserTokenModel<'a> {
     fn default() -> Self {
         Self {
             id: cuid().unwrap(),
-            token: None,
-            refresh_token: None,
+            token_hash: None,
+            refresh_token_hash: None,
             user_id: None,
             refresh_expire_at: None,
+            user_agent: None,
+            ip_address: None,
+            family_id: None,
         }
     }
 }
 
 impl<'a> NewUserTokenModel<'a> {
     pub fn new(
-        token: Option<&'a String>,
-        refresh_token: Option<&'a String>,
+        id: String,
+        token_hash: Option<&'a String>,
+        refresh_token_hash: Option<&'a String>,
         user_id: Option<&'a String>,
+        user_agent: Option<&'a String>,
+        ip_address: Option<&'a String>,
     ) -> Self {
-        let refresh_expire_at = Utc::now()
-            .naive_local()
-            .checked_add_signed(Duration::minutes(DURATION_OF_REFRESH_TOKEN_IN_MINUTES));
         Self {
-            token,
-            refresh_token,
+            id,
+            token_hash,
+            refresh_token_hash,
             user_id,
-            refresh_expire_at,
+            refresh_expire_at: Some(refresh_expiration(Utc::now().naive_local())),
+            user_agent,
+            ip_address,
+            // Each login starts a new family, kept by the tokens rotated from it
+            family_id: Some(cuid().unwrap()),
             ..Default::default()
         }
     }
@@ -70,3 +157,53 @@ impl<'a> NewUserTokenModel<'a> {
         user_tokens.filter(id.eq(self.id)).first(conn)
     }
 }
+
+/// A refresh token already exchanged, presenting it again means it was stolen
+#[derive(Identifiable, Associations, Queryable, Clone, Debug)]
+#[belongs_to(User)]
+#[primary_key(refresh_token_hash)]
+#[table_name = "used_refresh_tokens"]
+pub struct UsedRefreshTokenModel {
+    pub refresh_token_hash: String,
+    pub family_id: String,
+    pub user_id: String,
+    pub used_at: NaiveDateTime,
+}
+
+impl UsedRefreshTokenModel {
+    pub fn find(
+        hash: &str,
+        conn: &MysqlConnection,
+    ) -> Result<Option<UsedRefreshTokenModel>, Error> {
+        use crate::schema::used_refresh_tokens::dsl::*;
+        used_refresh_tokens
+            .filter(refresh_token_hash.eq(hash))
+            .first(conn)
+            .optional()
+    }
+}
+
+#[derive(Insertable)]
+#[table_name = "used_refresh_tokens"]
+pub struct NewUsedRefreshTokenModel<'a> {
+    pub refresh_token_hash: String,
+    pub family_id: &'a str,
+    pub user_id: &'a str,
+}
+
+impl<'a> NewUsedRefreshTokenModel<'a> {
+    pub fn new(refresh_token_hash: String, family_id: &'a str, user_id: &'a str) -> Self {
+        Self {
+            refresh_token_hash,
+            family_id,
+            user_id,
+        }
+    }
+
+    pub fn save(self, conn: &MysqlConnection) -> Result<usize, Error> {
+        use crate::schema::used_refresh_tokens::dsl::*;
+        diesel::insert_into(used_refresh_tokens)
+            .values(&self)
+            .execute(conn)
+    }
+}
@@ -6,6 +6,18 @@ table! {
     }
 }
 
+table! {
+    audit_events (id) {
+        id -> Varchar,
+        user_id -> Nullable<Varchar>,
+        event -> Varchar,
+        ip_address -> Nullable<Varchar>,
+        user_agent -> Nullable<Varchar>,
+        details -> Nullable<Text>,
+        created_at -> Timestamp,
+    }
+}
+
 table! {
     auth_items (name) {
         name -> Varchar,
@@ -24,6 +36,18 @@ table! {
     }
 }
 
+table! {
+    data_exports (id) {
+        id -> Varchar,
+        user_id -> Varchar,
+        requested_by -> Varchar,
+        status -> Varchar,
+        created_at -> Timestamp,
+        completed_at -> Nullable<Timestamp>,
+        expires_at -> Nullable<Timestamp>,
+    }
+}
+
 table! {
     use diesel::sql_types::*;
     use crate::models::external_user_provider::UserProviderMapping;
@@ -39,39 +63,142 @@ table! {
     }
 }
 
+table! {
+    oauth_clients (id) {
+        id -> Varchar,
+        name -> Varchar,
+        secret_hash -> Char,
+        scopes -> Text,
+        created_by -> Nullable<Varchar>,
+        created_at -> Timestamp,
+        updated_at -> Timestamp,
+        revoked_at -> Nullable<Timestamp>,
+    }
+}
+
+table! {
+    password_reset_tokens (id) {
+        id -> Varchar,
+        user_id -> Varchar,
+        token_hash -> Char,
+        expires_at -> Timestamp,
+        used_at -> Nullable<Timestamp>,
+        created_at -> Timestamp,
+    }
+}
+
+table! {
+    personal_access_tokens (id) {
+        id -> Varchar,
+        user_id -> Varchar,
+        name -> Varchar,
+        token_hash -> Char,
+        scopes -> Text,
+        expires_at -> Nullable<Timestamp>,
+        last_used_at -> Nullable<Timestamp>,
+        created_at -> Timestamp,
+        revoked_at -> Nullable<Timestamp>,
+    }
+}
+
 table! {
     users (id) {
         id -> Varchar,
-        hash -> Blob,
-        salt -> Varchar,
+        hash -> Nullable<Blob>,
+        salt -> Nullable<Varchar>,
         email -> Varchar,
         created_at -> Timestamp,
         updated_at -> Timestamp,
         deleted -> Bool,
+        email_verified_at -> Nullable<Timestamp>,
+        password_hash -> Nullable<Varchar>,
+    }
+}
+
+table! {
+    user_mfa (user_id) {
+        user_id -> Varchar,
+        secret_encrypted -> Blob,
+        last_used_step -> Nullable<Bigint>,
+        enabled_at -> Nullable<Timestamp>,
+        created_at -> Timestamp,
+        updated_at -> Timestamp,
+    }
+}
+
+table! {
+    user_mfa_recovery_codes (id) {
+        id -> Varchar,
+        user_id -> Varchar,
+        code_hash -> Char,
+        used_at -> Nullable<Timestamp>,
+        created_at -> Timestamp,
+    }
+}
+
+table! {
+    user_profiles (user_id) {
+        user_id -> Varchar,
+        display_name -> Nullable<Varchar>,
+        locale -> Nullable<Varchar>,
+        timezone -> Nullable<Varchar>,
+        avatar_url -> Nullable<Varchar>,
+        created_at -> Timestamp,
+        updated_at -> Timestamp,
+        avatar_key -> Nullable<Varchar>,
     }
 }
 
 table! {
     user_tokens (id) {
         id -> Varchar,
-        token -> Mediumtext,
-        refresh_token -> Mediumtext,
         user_id -> Varchar,
         refresh_expire_at -> Timestamp,
         created_at -> Timestamp,
         updated_at -> Timestamp,
+        user_agent -> Nullable<Varchar>,
+        ip_address -> Nullable<Varchar>,
+        last_refreshed_at -> Nullable<Timestamp>,
+        family_id -> Varchar,
+        token_hash -> Char,
+        refresh_token_hash -> Char,
+    }
+}
+
+table! {
+    used_refresh_tokens (refresh_token_hash) {
+        refresh_token_hash -> Char,
+        family_id -> Varchar,
+        user_id -> Varchar,
+        used_at -> Timestamp,
     }
 }
 
 joinable!(auth_assignments -> auth_items (item_name));
+joinable!(data_exports -> users (user_id));
 joinable!(external_user_providers -> users (user_id));
+joinable!(password_reset_tokens -> users (user_id));
+joinable!(personal_access_tokens -> users (user_id));
+joinable!(user_mfa -> users (user_id));
+joinable!(user_mfa_recovery_codes -> users (user_id));
+joinable!(user_profiles -> users (user_id));
+joinable!(used_refresh_tokens -> users (user_id));
 joinable!(user_tokens -> users (user_id));
 
 allow_tables_to_appear_in_same_query!(
+    audit_events,
     auth_assignments,
     auth_items,
     auth_item_children,
+    data_exports,
     external_user_providers,
+    oauth_clients,
+    password_reset_tokens,
+    personal_access_tokens,
+    used_refresh_tokens,
     users,
+    user_mfa,
+    user_mfa_recovery_codes,
+    user_profiles,
     user_tokens,
 );
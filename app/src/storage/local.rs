@@ -0,0 +1,79 @@
+use crate::storage::{BlobStore, StorageError};
+use std::fs;
+use std::io;
+use std::path::{Component, Path, PathBuf};
+
+/// The route that serves the files of the [`LocalBlobStore`]
+pub const UPLOADS_PATH: &str = "/uploads";
+
+/// Stores the files in a local directory, they are served by the `/uploads` static route
+pub struct LocalBlobStore {
+    dir: PathBuf,
+    base_url: String,
+}
+
+impl LocalBlobStore {
+    pub fn new(dir: &str, base_url: &str) -> Self {
+        // The static route only serves a directory that exists when the server starts
+        fs::create_dir_all(dir).unwrap_or_else(|error| panic!("Can't create {}: {}", dir, error));
+        Self {
+            dir: PathBuf::from(dir),
+            base_url: base_url.trim_end_matches('/').to_string(),
+        }
+    }
+
+    /// The path of a key, keys are never allowed to leave the directory
+    fn path(&self, key: &str) -> Result<PathBuf, StorageError> {
+        let key = Path::new(key);
+        if key
+            .components()
+            .all(|component| matches!(component, Component::Normal(_)))
+        {
+            Ok(self.dir.join(key))
+        } else {
+            Err(StorageError::Io(format!("invalid key {}", key.display())))
+        }
+    }
+}
+
+fn io_error(error: io::Error) -> StorageError {
+    StorageError::Io(error.to_string())
+}
+
+impl BlobStore for LocalBlobStore {
+    fn put(&self, key: &str, content: &[u8]) -> Result<(), StorageError> {
+        let path = self.path(key)?;
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(io_error)?;
+        }
+        fs::write(path, content).map_err(io_error)
+    }
+
+    fn delete_prefix(&self, prefix: &str) -> Result<(), StorageError> {
+        let path = self.path(prefix)?;
+        let (dir, name_prefix) = match (path.parent(), path.file_name()) {
+            (Some(dir), Some(name)) => (dir, name.to_string_lossy().to_string()),
+            _ => return Ok(()),
+        };
+        let entries = match fs::read_dir(dir) {
+            Ok(entries) => entries,
+            Err(error) if error.kind() == io::ErrorKind::NotFound => return Ok(()),
+            Err(error) => return Err(io_error(error)),
+        };
+        for entry in entries {
+            let entry = entry.map_err(io_error)?;
+            if entry
+                .file_name()
+                .to_string_lossy()
+                .starts_with(&name_prefix)
+            {
+                fs::remove_file(entry.path()).map_err(io_error)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn url(&self, key: &str) -> String {
+        format!("{}/{}", self.base_url, key)
+    }
+}
@@ -0,0 +1,39 @@
+pub mod local;
+
+use crate::errors::SrvError;
+use crate::utils::env::ENV;
+use std::sync::Arc;
+
+pub type ArcBlobStore = Arc<Box<dyn BlobStore>>;
+
+#[derive(Debug)]
+pub enum StorageError {
+    Io(String),
+}
+
+impl From<StorageError> for SrvError {
+    fn from(error: StorageError) -> SrvError {
+        println!("{:?}", error);
+        SrvError::InternalServerError
+    }
+}
+
+/// A place where the uploaded files are stored, each file is identified by a key such as `avatars/<user>/<file>`
+pub trait BlobStore: Send + Sync {
+    fn put(&self, key: &str, content: &[u8]) -> Result<(), StorageError>;
+    /// Removes every file whose key starts with `prefix`
+    fn delete_prefix(&self, prefix: &str) -> Result<(), StorageError>;
+    /// The public URL of a file
+    fn url(&self, key: &str) -> String;
+}
+
+/// Creates the [`BlobStore`] configured by `BLOB_STORE`
+pub fn connect() -> Box<dyn BlobStore> {
+    match ENV.blob_store.as_str() {
+        "local" => Box::new(local::LocalBlobStore::new(
+            &ENV.blob_store_dir,
+            &format!("{}{}", ENV.api_url, local::UPLOADS_PATH),
+        )),
+        other => panic!("BLOB_STORE {} is not supported", other),
+    }
+}
@@ -1,22 +1,65 @@
+use argon2::{Config, ThreadMode, Variant, Version};
 use argon2rs::argon2i_simple;
 use rand::{self, Rng};
 
-const CHARSET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ\
-                            abcdefghijklmnopqrstuvwxyz\
-                            0123456789)(*&^%$#@!~";
+// The costs are stored in every PHC string, so they can be raised without breaking old hashes
+const MEMORY_COST_IN_KIB: u32 = 65536;
+const TIME_COST: u32 = 3;
+const LANES: u32 = 4;
+const HASH_LENGTH: u32 = 32;
 
-const PASSWORD_LEN: usize = 128;
+fn config() -> Config<'static> {
+    Config {
+        variant: Variant::Argon2id,
+        version: Version::Version13,
+        mem_cost: MEMORY_COST_IN_KIB,
+        time_cost: TIME_COST,
+        lanes: LANES,
+        thread_mode: ThreadMode::Sequential,
+        secret: &[],
+        ad: &[],
+        hash_length: HASH_LENGTH,
+    }
+}
+
+/// Hashes a password with Argon2id, returns a PHC string with the salt and the parameters
+pub fn hash_password(password: &str) -> String {
+    let salt: [u8; 16] = rand::thread_rng().gen();
+    argon2::hash_encoded(password.as_bytes(), &salt, &config())
+        .expect("The Argon2 configuration is invalid")
+}
+
+/// Verifies a password against a PHC string, the comparison is constant-time
+pub fn verify_password(password: &str, encoded: &str) -> bool {
+    argon2::verify_encoded(encoded, password.as_bytes()).unwrap_or(false)
+}
+
+lazy_static! {
+    // Created with the current parameters, so verifying against it costs as much as a real hash
+    static ref DUMMY_PASSWORD_HASH: String = hash_password("dummy password");
+}
+
+/// Spends the time of a password verification when there is no hash to verify against, so the
+/// response time doesn't reveal if an account exists or has a password
+pub fn verify_dummy_password(password: &str) {
+    verify_password(password, &DUMMY_PASSWORD_HASH);
+}
+
+/// If a PHC string was created with other parameters than the current ones
+pub fn needs_rehash(encoded: &str) -> bool {
+    let prefix = format!(
+        "$argon2id$v=19$m={},t={},p={}$",
+        MEMORY_COST_IN_KIB, TIME_COST, LANES
+    );
+    !encoded.starts_with(&prefix)
+}
 
-pub fn make_salt() -> String {
-    let mut rng = rand::thread_rng();
-    (0..PASSWORD_LEN)
-        .map(|_| {
-            let idx = rng.gen_range(0, CHARSET.len());
-            CHARSET[idx] as char
-        })
-        .collect()
+/// Verifies a password against the raw Argon2i hash and salt stored before the PHC strings
+pub fn verify_legacy_password(password: &str, salt: &str, hash: &[u8]) -> bool {
+    constant_time_eq(&argon2i_simple(password, salt), hash)
 }
 
-pub fn make_hash(password: &str, salt: &str) -> Vec<u8> {
-    argon2i_simple(password, salt).to_vec()
+/// Compares two secrets without leaking where they differ through the timing
+pub fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
 }
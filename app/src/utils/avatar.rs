@@ -0,0 +1,110 @@
+//! Avatars are decoded and encoded again, so only clean PNG files without metadata are served
+use crate::errors::SrvError;
+use crate::storage::BlobStore;
+use crate::utils::env::ENV;
+use cuid::cuid;
+use image::imageops::FilterType;
+use image::{ImageFormat, ImageOutputFormat};
+use std::borrow::Cow;
+use std::io::{Cursor, Read};
+use validator::{ValidationError, ValidationErrors};
+
+/// Every avatar is stored as squares of these sizes in pixels
+pub const AVATAR_SIZES: [u32; 3] = [64, 128, 256];
+
+const ACCEPTED_FORMATS: [(&str, ImageFormat); 3] = [
+    ("image/png", ImageFormat::Png),
+    ("image/jpeg", ImageFormat::Jpeg),
+    ("image/gif", ImageFormat::Gif),
+];
+
+// Larger images are refused before being decoded, so a small file can't fill the memory
+const MAX_DIMENSION: u32 = 4096;
+
+fn invalid(code: &'static str, message: String) -> SrvError {
+    let mut error = ValidationError::new(code);
+    error.message = Some(Cow::from(message));
+    let mut errors = ValidationErrors::new();
+    errors.add("file", error);
+    SrvError::ValidationError(errors)
+}
+
+/// The key of an avatar in the [`BlobStore`]
+pub fn avatar_key(prefix: &str, size: u32) -> String {
+    format!("{}-{}.png", prefix, size)
+}
+
+/// The prefix shared by every avatar of a User
+pub fn user_avatars_prefix(user_id: &str) -> String {
+    format!("avatars/{}/", user_id)
+}
+
+/// Reads an uploaded file up to the configured limit
+pub fn read_upload(content: impl Read) -> Result<Vec<u8>, SrvError> {
+    let mut buffer = Vec::new();
+    content
+        .take(ENV.avatar_max_size_in_bytes + 1)
+        .read_to_end(&mut buffer)
+        .map_err(|_| SrvError::InternalServerError)?;
+    if buffer.len() as u64 > ENV.avatar_max_size_in_bytes {
+        return Err(invalid(
+            "file_too_large",
+            format!(
+                "The file must have at most {} bytes",
+                ENV.avatar_max_size_in_bytes
+            ),
+        ));
+    }
+    Ok(buffer)
+}
+
+/// Validates the image, stores it in every size and returns the prefix of the stored files
+///
+/// # Arguments
+/// * `store` - Where the files are stored
+/// * `user_id` - The owner of the avatar
+/// * `content_type` - The content type sent with the file, it must match the file content
+/// * `content` - The uploaded file
+pub fn store_avatar(
+    store: &dyn BlobStore,
+    user_id: &str,
+    content_type: Option<&str>,
+    content: &[u8],
+) -> Result<String, SrvError> {
+    let unsupported = || {
+        invalid(
+            "unsupported_image",
+            String::from("The file must be a PNG, JPEG or GIF image"),
+        )
+    };
+    let format = ACCEPTED_FORMATS
+        .iter()
+        .find(|(accepted, _)| Some(*accepted) == content_type)
+        .map(|(_, format)| *format)
+        .filter(|format| image::guess_format(content).ok() == Some(*format))
+        .ok_or_else(unsupported)?;
+    let (width, height) = image::io::Reader::with_format(Cursor::new(content), format)
+        .into_dimensions()
+        .map_err(|_| unsupported())?;
+    if width > MAX_DIMENSION || height > MAX_DIMENSION {
+        return Err(invalid(
+            "image_too_large",
+            format!(
+                "The image must have at most {}x{} pixels",
+                MAX_DIMENSION, MAX_DIMENSION
+            ),
+        ));
+    }
+    let image = image::load_from_memory_with_format(content, format).map_err(|_| unsupported())?;
+    // A new prefix on every upload, so the old URLs are never served from a cache
+    let prefix = format!("{}{}", user_avatars_prefix(user_id), cuid().unwrap());
+    for size in AVATAR_SIZES.iter() {
+        let mut encoded = Vec::new();
+        image
+            .resize_to_fill(*size, *size, FilterType::Lanczos3)
+            .write_to(&mut encoded, ImageOutputFormat::Png)
+            .map_err(|_| SrvError::InternalServerError)?;
+        store.put(&avatar_key(&prefix, *size), &encoded)?;
+    }
+    Ok(prefix)
+}
@@ -0,0 +1,38 @@
+//! Encryption of secrets stored in the database with AES-256-GCM and the `SECRETS_ENCRYPTION_KEY`
+use crate::errors::SrvError;
+use crate::utils::env::ENV;
+use aes_gcm::aead::{generic_array::GenericArray, Aead, NewAead};
+use aes_gcm::Aes256Gcm;
+use rand::{self, Rng};
+
+const KEY_LEN: usize = 32;
+const NONCE_LEN: usize = 12;
+
+fn cipher() -> Result<Aes256Gcm, SrvError> {
+    match hex::decode(&ENV.secrets_encryption_key) {
+        Ok(key) if key.len() == KEY_LEN => Ok(Aes256Gcm::new(GenericArray::from_slice(&key))),
+        // Features that store secrets are not available without a valid key
+        _ => Err(SrvError::Unavailable),
+    }
+}
+
+/// Encrypts a secret, the random nonce is stored before the cipher text
+pub fn encrypt(plain: &[u8]) -> Result<Vec<u8>, SrvError> {
+    let nonce: [u8; NONCE_LEN] = rand::thread_rng().gen();
+    let cipher_text = cipher()?
+        .encrypt(GenericArray::from_slice(&nonce), plain)
+        .map_err(|_| SrvError::InternalServerError)?;
+    let mut data = nonce.to_vec();
+    data.extend(cipher_text);
+    Ok(data)
+}
+
+pub fn decrypt(data: &[u8]) -> Result<Vec<u8>, SrvError> {
+    if data.len() < NONCE_LEN {
+        return Err(SrvError::InternalServerError);
+    }
+    let (nonce, cipher_text) = data.split_at(NONCE_LEN);
+    cipher()?
+        .decrypt(GenericArray::from_slice(nonce), cipher_text)
+        .map_err(|_| SrvError::InternalServerError)
+}
@@ -1,14 +1,57 @@
 use dotenv::dotenv;
 use std::env;
 
+/// If Users with an unverified E-mail are allowed to login
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum EmailVerificationPolicy {
+    Optional,
+    Required,
+}
+
+/// Only accepted when `RUST_ENV` is `dev`
+pub const DEFAULT_JWT_PRIVATE_KEY: &str = "my secret";
+
 pub struct EnvironmentValues {
     pub domain: String,
+    pub app_url: String,
+    pub api_url: String,
     pub redis_url: String,
     pub database_url: String,
     pub jwt_private_key: String,
+    pub jwt_keys: Vec<String>,
+    pub jwt_signing_kid: String,
     pub server_port: i16,
+    pub trusted_proxies: Vec<String>,
     pub rust_env: String,
     pub api_version_date: String,
+    pub email_verification_policy: EmailVerificationPolicy,
+    pub password_min_length: usize,
+    pub password_max_length: usize,
+    pub password_required_classes: Vec<String>,
+    pub password_forbid_email: bool,
+    pub breached_passwords_dir: String,
+    pub secrets_encryption_key: String,
+    pub mfa_issuer: String,
+    pub data_exports_dir: String,
+    pub data_export_ttl_in_hours: i64,
+    pub blob_store: String,
+    pub blob_store_dir: String,
+    pub avatar_max_size_in_bytes: u64,
+    pub mail_transport: String,
+    pub mail_from: String,
+    pub mail_outbox_dir: String,
+    pub smtp_host: String,
+    pub smtp_port: u16,
+    pub smtp_security: String,
+    pub smtp_username: String,
+    pub smtp_password: String,
+    pub google_client_ids: Vec<String>,
+    pub google_jwks_source: String,
+    pub facebook_graph_url: String,
+    pub facebook_app_id: String,
+    pub facebook_app_secret: String,
+    pub apple_client_ids: Vec<String>,
+    pub apple_jwks_source: String,
 }
 
 impl EnvironmentValues {
@@ -16,19 +59,87 @@ impl EnvironmentValues {
         dotenv().ok();
         Self {
             domain: env::var("DOMAIN").unwrap_or("localhost".into()),
+            app_url: env::var("APP_URL").unwrap_or("http://localhost:8080".into()),
+            api_url: env::var("API_URL").unwrap_or("http://localhost".into()),
             database_url: env::var("DATABASE_URL").expect("DATABASE_URL must be set"),
             redis_url: env::var("REDIS_URL").expect("REDIS_URL must be set"),
-            jwt_private_key: env::var("JWT_PRIVATE_KEY").unwrap_or("my secret".into()),
+            jwt_private_key: env::var("JWT_PRIVATE_KEY").unwrap_or(DEFAULT_JWT_PRIVATE_KEY.into()),
+            jwt_keys: list_var("JWT_KEYS"),
+            jwt_signing_kid: env::var("JWT_SIGNING_KID").unwrap_or_default(),
             server_port: env::var("SERVER_PORT")
                 .unwrap_or_else(|_| String::from("80"))
                 .parse()
                 .expect("SERVER_PORT must be a number"),
+            trusted_proxies: list_var("TRUSTED_PROXIES"),
             rust_env: env::var("RUST_ENV").unwrap_or("dev".into()),
             api_version_date: env::var("API_VERSION_DATE").unwrap_or("2020-03-31".into()),
+            email_verification_policy: match env::var("EMAIL_VERIFICATION_POLICY")
+                .unwrap_or_default()
+                .as_str()
+            {
+                "required" => EmailVerificationPolicy::Required,
+                _ => EmailVerificationPolicy::Optional,
+            },
+            password_min_length: env::var("PASSWORD_MIN_LENGTH")
+                .unwrap_or_else(|_| String::from("8"))
+                .parse()
+                .expect("PASSWORD_MIN_LENGTH must be a number"),
+            password_max_length: env::var("PASSWORD_MAX_LENGTH")
+                .unwrap_or_else(|_| String::from("128"))
+                .parse()
+                .expect("PASSWORD_MAX_LENGTH must be a number"),
+            password_required_classes: list_var("PASSWORD_REQUIRED_CLASSES"),
+            password_forbid_email: env::var("PASSWORD_FORBID_EMAIL")
+                .map_or(true, |value| value != "false"),
+            breached_passwords_dir: env::var("BREACHED_PASSWORDS_DIR").unwrap_or_default(),
+            secrets_encryption_key: env::var("SECRETS_ENCRYPTION_KEY").unwrap_or_default(),
+            mfa_issuer: env::var("MFA_ISSUER").unwrap_or("rust-graphql-example".into()),
+            data_exports_dir: env::var("DATA_EXPORTS_DIR").unwrap_or("exports".into()),
+            data_export_ttl_in_hours: env::var("DATA_EXPORT_TTL_IN_HOURS")
+                .unwrap_or_else(|_| String::from("72"))
+                .parse()
+                .expect("DATA_EXPORT_TTL_IN_HOURS must be a number"),
+            blob_store: env::var("BLOB_STORE").unwrap_or("local".into()),
+            blob_store_dir: env::var("BLOB_STORE_DIR").unwrap_or("uploads".into()),
+            avatar_max_size_in_bytes: env::var("AVATAR_MAX_SIZE_IN_BYTES")
+                .unwrap_or_else(|_| String::from("5242880"))
+                .parse()
+                .expect("AVATAR_MAX_SIZE_IN_BYTES must be a number"),
+            mail_transport: env::var("MAIL_TRANSPORT").unwrap_or("file".into()),
+            mail_from: env::var("MAIL_FROM").unwrap_or("no-reply@localhost".into()),
+            mail_outbox_dir: env::var("MAIL_OUTBOX_DIR").unwrap_or("outbox".into()),
+            smtp_host: env::var("SMTP_HOST").unwrap_or("localhost".into()),
+            smtp_port: env::var("SMTP_PORT")
+                .unwrap_or_else(|_| String::from("587"))
+                .parse()
+                .expect("SMTP_PORT must be a number"),
+            smtp_security: env::var("SMTP_SECURITY").unwrap_or("starttls".into()),
+            smtp_username: env::var("SMTP_USERNAME").unwrap_or_default(),
+            smtp_password: env::var("SMTP_PASSWORD").unwrap_or_default(),
+            google_client_ids: list_var("GOOGLE_CLIENT_IDS"),
+            google_jwks_source: env::var("GOOGLE_JWKS_SOURCE")
+                .unwrap_or("https://www.googleapis.com/oauth2/v3/certs".into()),
+            facebook_graph_url: env::var("FACEBOOK_GRAPH_URL")
+                .unwrap_or("https://graph.facebook.com/v7.0".into()),
+            facebook_app_id: env::var("FACEBOOK_APP_ID").unwrap_or_default(),
+            facebook_app_secret: env::var("FACEBOOK_APP_SECRET").unwrap_or_default(),
+            apple_client_ids: list_var("APPLE_CLIENT_IDS"),
+            apple_jwks_source: env::var("APPLE_JWKS_SOURCE")
+                .unwrap_or("https://appleid.apple.com/auth/keys".into()),
         }
     }
 }
 
+/// Reads a comma separated environment variable, an unset variable is an empty list
+fn list_var(key: &str) -> Vec<String> {
+    env::var(key)
+        .unwrap_or_default()
+        .split(',')
+        .map(|value| value.trim().to_string())
+        .filter(|value| !value.is_empty())
+        .collect()
+}
+
 lazy_static! {
     pub static ref ENV: EnvironmentValues = EnvironmentValues::init();
 }
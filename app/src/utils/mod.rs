@@ -1,2 +1,10 @@
 pub mod argon;
+pub mod avatar;
+pub mod encryption;
 pub mod env;
+pub mod password_policy;
+pub mod profile;
+pub mod revocation;
+pub mod throttle;
+pub mod token;
+pub mod totp;
@@ -0,0 +1,294 @@
+//! Rules that every new password must follow, configured from the environment
+use crate::errors::SrvError;
+use crate::utils::env::ENV;
+use sha1::{Digest, Sha1};
+use std::borrow::Cow;
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
+use validator::{Validate, ValidationError, ValidationErrors};
+
+lazy_static! {
+    pub static ref PASSWORD_POLICY: PasswordPolicy = PasswordPolicy::from_env();
+}
+
+// Length of the SHA-1 prefix that names each file of the breached passwords directory
+const BREACHED_PREFIX_LENGTH: usize = 5;
+
+/// A kind of character that can be required in the passwords
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CharacterClass {
+    Lowercase,
+    Uppercase,
+    Digit,
+    Symbol,
+}
+
+impl CharacterClass {
+    fn from_config(value: &str) -> Option<Self> {
+        match value {
+            "lowercase" => Some(CharacterClass::Lowercase),
+            "uppercase" => Some(CharacterClass::Uppercase),
+            "digit" => Some(CharacterClass::Digit),
+            "symbol" => Some(CharacterClass::Symbol),
+            _ => None,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            CharacterClass::Lowercase => "lowercase",
+            CharacterClass::Uppercase => "uppercase",
+            CharacterClass::Digit => "digit",
+            CharacterClass::Symbol => "symbol",
+        }
+    }
+
+    fn matches(self, character: char) -> bool {
+        match self {
+            CharacterClass::Lowercase => character.is_lowercase(),
+            CharacterClass::Uppercase => character.is_uppercase(),
+            CharacterClass::Digit => character.is_numeric(),
+            CharacterClass::Symbol => !character.is_alphanumeric(),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct PasswordPolicy {
+    pub min_length: usize,
+    pub max_length: usize,
+    pub required_classes: Vec<CharacterClass>,
+    pub forbid_email: bool,
+    pub breached_passwords_dir: Option<PathBuf>,
+}
+
+impl PasswordPolicy {
+    fn from_env() -> Self {
+        Self {
+            min_length: ENV.password_min_length,
+            max_length: ENV.password_max_length,
+            required_classes: ENV
+                .password_required_classes
+                .iter()
+                .map(|class| {
+                    CharacterClass::from_config(class).unwrap_or_else(|| {
+                        panic!("PASSWORD_REQUIRED_CLASSES has an unknown class {}", class)
+                    })
+                })
+                .collect(),
+            forbid_email: ENV.password_forbid_email,
+            breached_passwords_dir: Some(&ENV.breached_passwords_dir)
+                .filter(|dir| !dir.is_empty())
+                .map(PathBuf::from),
+        }
+    }
+
+    /// Returns every rule broken by the password
+    ///
+    /// # Arguments
+    /// * `password` - The new password
+    /// * `email` - The E-mail of the User, its local-part can't be in the password
+    pub fn violations(&self, password: &str, email: &str) -> Vec<ValidationError> {
+        let mut violations = Vec::new();
+        let length = password.chars().count();
+        if length < self.min_length {
+            violations.push(violation(
+                "password_too_short",
+                format!(
+                    "The password must have at least {} characters",
+                    self.min_length
+                ),
+            ));
+        }
+        if length > self.max_length {
+            violations.push(violation(
+                "password_too_long",
+                format!(
+                    "The password must have at most {} characters",
+                    self.max_length
+                ),
+            ));
+        }
+        for class in &self.required_classes {
+            if !password.chars().any(|character| class.matches(character)) {
+                let mut error = violation(
+                    "password_missing_class",
+                    format!("The password must have a {} character", class.name()),
+                );
+                error.add_param(Cow::from("class"), &class.name());
+                violations.push(error);
+            }
+        }
+        let local_part = email.split('@').next().unwrap_or_default().to_lowercase();
+        if self.forbid_email
+            && !local_part.is_empty()
+            && password.to_lowercase().contains(&local_part)
+        {
+            violations.push(violation(
+                "password_contains_email",
+                String::from("The password can't contain the E-mail"),
+            ));
+        }
+        if let Some(dir) = &self.breached_passwords_dir {
+            if is_breached(dir, password) {
+                violations.push(violation(
+                    "password_breached",
+                    String::from("This password appeared in a data breach, choose another one"),
+                ));
+            }
+        }
+        violations
+    }
+}
+
+fn violation(code: &'static str, message: String) -> ValidationError {
+    let mut error = ValidationError::new(code);
+    error.message = Some(Cow::from(message));
+    error
+}
+
+/// Looks for the password in a directory of k-anonymity range files, as served by the
+/// Pwned Passwords range API: each file is named by the first 5 hex characters of the SHA-1
+/// and has a `SUFFIX:COUNT` line for every breached password with that prefix
+fn is_breached(dir: &Path, password: &str) -> bool {
+    let digest = hex::encode_upper(Sha1::digest(password.as_bytes()));
+    let (prefix, suffix) = digest.split_at(BREACHED_PREFIX_LENGTH);
+    let file = match std::fs::File::open(dir.join(prefix)) {
+        Ok(file) => file,
+        Err(_) => return false,
+    };
+    BufReader::new(file).lines().any(|line| {
+        line.map_or(false, |line| {
+            line.split(':')
+                .next()
+                .map_or(false, |hash| hash.trim().eq_ignore_ascii_case(suffix))
+        })
+    })
+}
+
+/// Validates an input and the password policy together, so every violation is returned at once
+///
+/// # Arguments
+/// * `input` - The input with the password
+/// * `field` - The name of the password field in the input
+/// * `password` - The new password, nothing is checked by the policy when it is not changed
+/// * `email` - The E-mail of the User
+pub fn validate_with_password_policy(
+    input: &impl Validate,
+    field: &'static str,
+    password: Option<&str>,
+    email: &str,
+) -> Result<(), SrvError> {
+    let mut errors = input.validate().err().unwrap_or_else(ValidationErrors::new);
+    if let Some(password) = password {
+        for violation in PASSWORD_POLICY.violations(password, email) {
+            errors.add(field, violation);
+        }
+    }
+    if errors.errors().is_empty() {
+        Ok(())
+    } else {
+        Err(SrvError::ValidationError(errors))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use uuid::Uuid;
+
+    fn policy() -> PasswordPolicy {
+        PasswordPolicy {
+            min_length: 8,
+            max_length: 16,
+            required_classes: vec![
+                CharacterClass::Lowercase,
+                CharacterClass::Uppercase,
+                CharacterClass::Digit,
+                CharacterClass::Symbol,
+            ],
+            forbid_email: true,
+            breached_passwords_dir: None,
+        }
+    }
+
+    fn codes(violations: Vec<ValidationError>) -> Vec<Cow<'static, str>> {
+        violations.into_iter().map(|error| error.code).collect()
+    }
+
+    #[test]
+    fn a_password_that_follows_every_rule_has_no_violations() {
+        assert!(policy()
+            .violations("Tr0ub4dor&3", "user@example.com")
+            .is_empty());
+    }
+
+    #[test]
+    fn every_broken_rule_is_returned() {
+        assert_eq!(
+            codes(policy().violations("user", "user@example.com")),
+            vec![
+                "password_too_short",
+                "password_missing_class",
+                "password_missing_class",
+                "password_missing_class",
+                "password_contains_email",
+            ]
+        );
+        assert_eq!(
+            codes(policy().violations("Tr0ub4dor&3Tr0ub4dor&3", "user@example.com")),
+            vec!["password_too_long"]
+        );
+    }
+
+    #[test]
+    fn the_length_is_counted_in_characters() {
+        assert!(policy()
+            .violations("Çãõ1!Çãõ", "user@example.com")
+            .is_empty());
+    }
+
+    #[test]
+    fn the_missing_class_is_a_parameter() {
+        let violations = policy().violations("TR0UB4DOR&3", "user@example.com");
+        assert_eq!(violations.len(), 1);
+        assert_eq!(violations[0].params["class"], "lowercase");
+    }
+
+    #[test]
+    fn the_email_is_only_checked_when_forbidden() {
+        let password = "Xx1!UsEr";
+        assert_eq!(
+            codes(policy().violations(password, "user@example.com")),
+            vec!["password_contains_email"]
+        );
+        let policy = PasswordPolicy {
+            forbid_email: false,
+            ..policy()
+        };
+        assert!(policy.violations(password, "user@example.com").is_empty());
+    }
+
+    #[test]
+    fn breached_passwords_are_found_in_their_range_file() {
+        let dir = std::env::temp_dir().join(format!("breached-passwords-{}", Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        // The SHA-1 of "Password1!" is in the range file of its first 5 hex characters
+        let digest = hex::encode_upper(Sha1::digest(b"Password1!"));
+        let (prefix, suffix) = digest.split_at(BREACHED_PREFIX_LENGTH);
+        std::fs::write(
+            dir.join(prefix),
+            format!("0000000000000000000000000000000000A:1\r\n{}:42\r\n", suffix),
+        )
+        .unwrap();
+        let policy = PasswordPolicy {
+            breached_passwords_dir: Some(dir.clone()),
+            ..policy()
+        };
+        let breached = codes(policy.violations("Password1!", "user@example.com"));
+        let not_breached = codes(policy.violations("Password2!", "user@example.com"));
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(breached, vec!["password_breached"]);
+        assert!(not_breached.is_empty());
+    }
+}
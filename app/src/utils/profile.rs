@@ -0,0 +1,121 @@
+//! Rules of the profile fields, shared by the profile inputs and the External Provider data
+use chrono_tz::Tz;
+use std::borrow::Cow;
+use url::Url;
+use validator::ValidationError;
+
+pub const DISPLAY_NAME_MAX_LENGTH: usize = 255;
+pub const AVATAR_URL_MAX_LENGTH: usize = 2048;
+const LOCALE_MAX_LENGTH: usize = 35;
+
+fn invalid(code: &'static str, message: &'static str) -> ValidationError {
+    let mut error = ValidationError::new(code);
+    error.message = Some(Cow::from(message));
+    error
+}
+
+/// A BCP 47 language tag such as `en` or `pt-BR`, an empty value clears the locale
+pub fn validate_locale(value: &str) -> Result<(), ValidationError> {
+    let mut subtags = value.split('-');
+    let language = subtags.next().unwrap_or_default();
+    let valid = value.len() <= LOCALE_MAX_LENGTH
+        && (2..=3).contains(&language.len())
+        && language.chars().all(|c| c.is_ascii_alphabetic())
+        && subtags.all(|subtag| {
+            (1..=8).contains(&subtag.len()) && subtag.chars().all(|c| c.is_ascii_alphanumeric())
+        });
+    if value.is_empty() || valid {
+        Ok(())
+    } else {
+        Err(invalid(
+            "locale",
+            "This value should be a locale such as en or pt-BR",
+        ))
+    }
+}
+
+/// An IANA time zone such as `America/Sao_Paulo`, an empty value clears the time zone
+pub fn validate_timezone(value: &str) -> Result<(), ValidationError> {
+    if value.is_empty() || value.parse::<Tz>().is_ok() {
+        Ok(())
+    } else {
+        Err(invalid("timezone", "This value should be a IANA time zone"))
+    }
+}
+
+/// An absolute `http` or `https` URL, an empty value clears the avatar
+pub fn validate_avatar_url(value: &str) -> Result<(), ValidationError> {
+    let valid = value.len() <= AVATAR_URL_MAX_LENGTH
+        && Url::parse(value).map_or(false, |url| {
+            (url.scheme() == "https" || url.scheme() == "http") && url.has_host()
+        });
+    if value.is_empty() || valid {
+        Ok(())
+    } else {
+        Err(invalid(
+            "avatar_url",
+            "This value should be a http or https URL",
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bcp_47_locales_are_accepted() {
+        for locale in &[
+            "",
+            "en",
+            "pt-BR",
+            "zh-Hant-TW",
+            "es-419",
+            "ast",
+            "de-CH-1901",
+        ] {
+            assert!(validate_locale(locale).is_ok(), "{}", locale);
+        }
+    }
+
+    #[test]
+    fn malformed_locales_are_refused() {
+        for locale in &[
+            "e",
+            "english",
+            "pt_BR",
+            "pt-",
+            "-BR",
+            "en--US",
+            "en-toolongsubtag",
+            "1a",
+            "ça",
+        ] {
+            let error = validate_locale(locale).unwrap_err();
+            assert_eq!(error.code, "locale", "{}", locale);
+        }
+        let too_long = format!("en{}", "-abcdefgh".repeat(4));
+        assert!(validate_locale(&too_long).is_err());
+    }
+
+    #[test]
+    fn time_zones_must_be_iana_names() {
+        assert!(validate_timezone("").is_ok());
+        assert!(validate_timezone("America/Sao_Paulo").is_ok());
+        assert!(validate_timezone("UTC").is_ok());
+        assert!(validate_timezone("Mars/Olympus_Mons").is_err());
+        assert!(validate_timezone("America/Nowhere").is_err());
+    }
+
+    #[test]
+    fn avatar_urls_must_be_absolute_http_urls() {
+        assert!(validate_avatar_url("").is_ok());
+        assert!(validate_avatar_url("https://example.com/avatar.png").is_ok());
+        assert!(validate_avatar_url("http://example.com/avatar.png").is_ok());
+        assert!(validate_avatar_url("/avatar.png").is_err());
+        assert!(validate_avatar_url("javascript:alert(1)").is_err());
+        assert!(validate_avatar_url("data:image/png;base64,AAAA").is_err());
+        let too_long = format!("https://example.com/{}", "a".repeat(AVATAR_URL_MAX_LENGTH));
+        assert!(validate_avatar_url(&too_long).is_err());
+    }
+}
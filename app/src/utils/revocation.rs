@@ -0,0 +1,48 @@
+//! Sessions revoked before their access tokens expire and used single use tokens, checked without
+//! touching the database
+use crate::errors::SrvError;
+use crate::web_utils::jwt::AUTH_TOKEN_DURATION_IN_HOURS;
+use redis::Commands;
+
+fn revoked_key(session_id: &str) -> String {
+    format!("auth:revoked:{}", session_id)
+}
+
+fn used_key(token_id: &str) -> String {
+    format!("auth:used:{}", token_id)
+}
+
+/// Marks the sessions as revoked, the mark outlives every access token issued for them
+pub fn revoke_sessions(client: &redis::Client, session_ids: &[String]) -> Result<(), SrvError> {
+    if session_ids.is_empty() {
+        return Ok(());
+    }
+    let mut conn = client.get_connection()?;
+    let ttl = (AUTH_TOKEN_DURATION_IN_HOURS * 3600) as usize;
+    for session_id in session_ids {
+        let _: () = conn.set_ex(revoked_key(session_id), 1, ttl)?;
+    }
+    Ok(())
+}
+
+pub fn is_revoked(client: &redis::Client, session_id: &str) -> Result<bool, SrvError> {
+    let mut conn = client.get_connection()?;
+    Ok(conn.exists(revoked_key(session_id))?)
+}
+
+/// Marks a single use token as used until it expires, false when it was already used
+pub fn use_once(
+    client: &redis::Client,
+    token_id: &str,
+    ttl_in_seconds: usize,
+) -> Result<bool, SrvError> {
+    let mut conn = client.get_connection()?;
+    let marked: Option<String> = redis::cmd("SET")
+        .arg(used_key(token_id))
+        .arg(1)
+        .arg("NX")
+        .arg("EX")
+        .arg(ttl_in_seconds)
+        .query(&mut conn)?;
+    Ok(marked.is_some())
+}
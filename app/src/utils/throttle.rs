@@ -0,0 +1,131 @@
+//! Attempt counters stored in Redis, used to slow down brute-force attacks
+use crate::errors::SrvError;
+use redis::Commands;
+
+// Failures are forgotten after this period without new failures
+const FAILURES_WINDOW_IN_SECONDS: usize = 3600;
+const BASE_LOCKOUT_IN_SECONDS: usize = 30;
+const MAX_LOCKOUT_IN_SECONDS: usize = 3600;
+
+/// What is being counted, each one has its own limit of failures before a lockout
+#[derive(Debug, Clone, Copy)]
+pub enum Subject<'a> {
+    Account(&'a str),
+    Ip(&'a str),
+}
+
+impl<'a> Subject<'a> {
+    fn key(&self) -> String {
+        match self {
+            Subject::Account(account) => format!("account:{}", account.to_lowercase()),
+            Subject::Ip(ip) => format!("ip:{}", ip),
+        }
+    }
+
+    fn allowed_failures(&self) -> usize {
+        match self {
+            Subject::Account(_) => 5,
+            Subject::Ip(_) => 20,
+        }
+    }
+}
+
+/// The lockout after the given failures, it doubles for every failure above the allowed ones
+fn lockout_in_seconds(failures: usize, allowed_failures: usize) -> Option<usize> {
+    if failures < allowed_failures {
+        return None;
+    }
+    let exponent = (failures - allowed_failures).min(16) as u32;
+    Some((BASE_LOCKOUT_IN_SECONDS * 2usize.pow(exponent)).min(MAX_LOCKOUT_IN_SECONDS))
+}
+
+/// Counts the failed attempts of an action, locking the subjects out with an exponential backoff
+pub struct Throttle<'a> {
+    client: &'a redis::Client,
+    action: &'static str,
+}
+
+impl<'a> Throttle<'a> {
+    pub fn new(client: &'a redis::Client, action: &'static str) -> Self {
+        Self { client, action }
+    }
+
+    fn failures_key(&self, subject: &Subject) -> String {
+        format!("throttle:{}:failures:{}", self.action, subject.key())
+    }
+
+    fn lock_key(&self, subject: &Subject) -> String {
+        format!("throttle:{}:lock:{}", self.action, subject.key())
+    }
+
+    /// Fails with [`SrvError::TooManyAttempts`] when any of the subjects is locked out
+    pub fn check(&self, subjects: &[Subject]) -> Result<(), SrvError> {
+        let mut conn = self.client.get_connection()?;
+        for subject in subjects {
+            let remaining: i64 = conn.ttl(self.lock_key(subject))?;
+            if remaining > 0 {
+                return Err(SrvError::TooManyAttempts(remaining as u64));
+            }
+        }
+        Ok(())
+    }
+
+    /// Counts a failed attempt, the lockout doubles for every failure above the allowed ones
+    pub fn register_failure(&self, subjects: &[Subject]) -> Result<(), SrvError> {
+        let mut conn = self.client.get_connection()?;
+        for subject in subjects {
+            let failures_key = self.failures_key(subject);
+            let failures: usize = conn.incr(&failures_key, 1)?;
+            let _: () = conn.expire(&failures_key, FAILURES_WINDOW_IN_SECONDS)?;
+            if let Some(lockout) = lockout_in_seconds(failures, subject.allowed_failures()) {
+                let _: () = conn.set_ex(self.lock_key(subject), failures, lockout)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Forgets the failures and removes the lockout of the subjects
+    pub fn reset(&self, subjects: &[Subject]) -> Result<(), SrvError> {
+        let mut conn = self.client.get_connection()?;
+        for subject in subjects {
+            let _: () = conn.del(vec![self.failures_key(subject), self.lock_key(subject)])?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn no_lockout_before_the_allowed_failures() {
+        assert_eq!(lockout_in_seconds(0, 5), None);
+        assert_eq!(lockout_in_seconds(4, 5), None);
+    }
+
+    #[test]
+    fn the_lockout_doubles_on_every_new_failure() {
+        assert_eq!(lockout_in_seconds(5, 5), Some(30));
+        assert_eq!(lockout_in_seconds(6, 5), Some(60));
+        assert_eq!(lockout_in_seconds(7, 5), Some(120));
+        assert_eq!(lockout_in_seconds(20, 20), Some(30));
+        assert_eq!(lockout_in_seconds(21, 20), Some(60));
+    }
+
+    #[test]
+    fn the_lockout_is_capped_at_one_hour() {
+        assert_eq!(lockout_in_seconds(11, 5), Some(1920));
+        assert_eq!(lockout_in_seconds(12, 5), Some(MAX_LOCKOUT_IN_SECONDS));
+        assert_eq!(lockout_in_seconds(1000, 5), Some(MAX_LOCKOUT_IN_SECONDS));
+    }
+
+    #[test]
+    fn accounts_are_counted_case_insensitively() {
+        assert_eq!(
+            Subject::Account("User@Example.com").key(),
+            "account:user@example.com"
+        );
+        assert_eq!(Subject::Ip("127.0.0.1").key(), "ip:127.0.0.1");
+    }
+}
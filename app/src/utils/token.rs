@@ -0,0 +1,15 @@
+use rand::{self, Rng};
+use sha2::{Digest, Sha256};
+
+const TOKEN_BYTES: usize = 32;
+
+/// Creates a random url safe token that can be sent to a User
+pub fn random_token() -> String {
+    let bytes: [u8; TOKEN_BYTES] = rand::thread_rng().gen();
+    hex::encode(bytes)
+}
+
+/// The SHA-256 digest of a token, only this value should be stored
+pub fn hash_token(token: &str) -> String {
+    hex::encode(Sha256::digest(token.as_bytes()))
+}
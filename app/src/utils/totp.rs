@@ -0,0 +1,97 @@
+//! Time-based One-Time Passwords as described in RFC 6238
+use hmac::{Hmac, Mac, NewMac};
+use rand::{self, Rng};
+use sha1::Sha1;
+use url::form_urlencoded::byte_serialize;
+
+const SECRET_LEN: usize = 20;
+const STEP_IN_SECONDS: i64 = 30;
+const DIGITS: u32 = 6;
+// Accepts the codes from the previous and the next step to tolerate clock drift
+const ALLOWED_STEP_DRIFT: i64 = 1;
+
+pub fn generate_secret() -> Vec<u8> {
+    let secret: [u8; SECRET_LEN] = rand::thread_rng().gen();
+    secret.to_vec()
+}
+
+/// Base32 representation of the secret used by authenticator apps
+pub fn encode_secret(secret: &[u8]) -> String {
+    base32::encode(base32::Alphabet::RFC4648 { padding: false }, secret)
+}
+
+/// The `otpauth://` URI that authenticator apps read from a QR code
+pub fn otpauth_uri(encoded_secret: &str, account: &str, issuer: &str) -> String {
+    let label: String = byte_serialize(format!("{}:{}", issuer, account).as_bytes()).collect();
+    let issuer: String = byte_serialize(issuer.as_bytes()).collect();
+    format!(
+        "otpauth://totp/{}?secret={}&issuer={}&algorithm=SHA1&digits={}&period={}",
+        label, encoded_secret, issuer, DIGITS, STEP_IN_SECONDS
+    )
+}
+
+fn code_at(secret: &[u8], step: i64) -> u32 {
+    let mut mac = Hmac::<Sha1>::new_varkey(secret).expect("HMAC accepts keys of any size");
+    mac.update(&step.to_be_bytes());
+    let hash = mac.finalize().into_bytes();
+    let offset = (hash[hash.len() - 1] & 0xf) as usize;
+    let binary = ((hash[offset] as u32 & 0x7f) << 24)
+        | ((hash[offset + 1] as u32) << 16)
+        | ((hash[offset + 2] as u32) << 8)
+        | hash[offset + 3] as u32;
+    binary % 10u32.pow(DIGITS)
+}
+
+/// Verifies a code at the given unix timestamp, returning the matched time step
+pub fn verify(secret: &[u8], code: &str, timestamp: i64) -> Option<i64> {
+    let code = code.trim();
+    if code.len() != DIGITS as usize {
+        return None;
+    }
+    let code: u32 = code.parse().ok()?;
+    let current_step = timestamp / STEP_IN_SECONDS;
+    (current_step - ALLOWED_STEP_DRIFT..=current_step + ALLOWED_STEP_DRIFT)
+        .find(|step| code_at(secret, *step) == code)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // The SHA-1 secret of the RFC 6238 test vectors
+    const SECRET: &[u8] = b"12345678901234567890";
+
+    #[test]
+    fn verify_accepts_the_rfc_6238_codes() {
+        assert_eq!(verify(SECRET, "287082", 59), Some(1));
+        assert_eq!(verify(SECRET, "081804", 1_111_111_109), Some(37_037_036));
+        assert_eq!(verify(SECRET, "050471", 1_111_111_111), Some(37_037_037));
+        assert_eq!(verify(SECRET, "005924", 1_234_567_890), Some(41_152_263));
+    }
+
+    #[test]
+    fn verify_tolerates_one_step_of_drift() {
+        // 287082 is the code of the step 1, from 30 to 59 seconds
+        assert_eq!(verify(SECRET, "287082", 59 - STEP_IN_SECONDS), Some(1));
+        assert_eq!(verify(SECRET, "287082", 59 + STEP_IN_SECONDS), Some(1));
+        assert_eq!(verify(SECRET, "287082", 59 + 2 * STEP_IN_SECONDS), None);
+    }
+
+    #[test]
+    fn verify_refuses_malformed_codes() {
+        assert_eq!(verify(SECRET, " 287082 ", 59), Some(1));
+        assert_eq!(verify(SECRET, "28708", 59), None);
+        assert_eq!(verify(SECRET, "2870822", 59), None);
+        assert_eq!(verify(SECRET, "28708a", 59), None);
+        assert_eq!(verify(b"another secret", "287082", 59), None);
+    }
+
+    #[test]
+    fn the_uri_has_the_encoded_label_and_issuer() {
+        assert_eq!(
+            otpauth_uri(&encode_secret(SECRET), "user@example.com", "My App"),
+            "otpauth://totp/My+App%3Auser%40example.com?secret=GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ\
+             &issuer=My+App&algorithm=SHA1&digits=6&period=30"
+        );
+    }
+}
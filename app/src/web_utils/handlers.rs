@@ -1,24 +1,53 @@
-use actix_web::{web, HttpRequest, HttpResponse, Result};
+use actix_web::{http::header, http::StatusCode, web, HttpRequest, HttpResponse, Result};
 use actix_web_actors::ws;
 use async_graphql::http::{playground_source, GQLResponse};
 use async_graphql::Data;
 use async_graphql_actix_web::{GQLRequest, WSSubscription};
+use serde_json::json;
 
 use crate::db::mysql::DbPool;
+use crate::errors::SrvError;
+use crate::graphql::utils::authorization::AuthorizationService;
 use crate::graphql::{context::Context, Schema};
-use crate::models::SlimUser;
-use crate::web_utils::jwt::{decode_token, token_from_value};
+use crate::jobs::data_export::archive_path;
+use crate::mail::MailService;
+use crate::models::{DataExportModel as DataExport, OAuthClientModel as OAuthClient, Scope};
+use crate::storage::BlobStore;
+use crate::utils::throttle::{Subject, Throttle};
+use crate::web_utils::jwt::{
+    create_client_token, decode_data_export_token, token_from_value, Principal,
+    CLIENT_TOKEN_DURATION_IN_MINUTES,
+};
+use crate::web_utils::request::RequestInfo;
+use crate::web_utils::signing_keys::KEYS;
 
 pub async fn gql(
     schema: web::Data<Schema>,
-    user: SlimUser,
+    req: HttpRequest,
+    principal: Principal,
     mysql: web::Data<DbPool>,
     redis: web::Data<redis::Client>,
+    mail: web::Data<MailService>,
+    blob_store: web::Data<Box<dyn BlobStore>>,
+    auth: web::Data<AuthorizationService>,
     gql_request: GQLRequest,
 ) -> web::Json<GQLResponse> {
     let pool = mysql.into_inner();
     let redis_client = redis.into_inner();
-    let ctx = Context::new(user, pool, redis_client);
+    let mail_service = mail.into_inner();
+    let blob_store = blob_store.into_inner();
+    let auth_service = auth.get_ref().clone();
+    // The loaded roles are kept when Redis is unavailable
+    auth_service.reload_if_changed(&redis_client).ok();
+    let ctx = Context::new(
+        principal,
+        pool,
+        redis_client,
+        mail_service,
+        blob_store,
+        auth_service,
+        RequestInfo::from_request(&req),
+    );
     let req = gql_request.into_inner().data(ctx);
     web::Json(GQLResponse(req.execute(&schema).await))
 }
@@ -28,18 +57,33 @@ pub async fn gql_subscriptions(
     req: HttpRequest,
     mysql: web::Data<DbPool>,
     redis: web::Data<redis::Client>,
+    mail: web::Data<MailService>,
+    blob_store: web::Data<Box<dyn BlobStore>>,
+    auth: web::Data<AuthorizationService>,
     payload: web::Payload,
 ) -> Result<HttpResponse> {
     let actor = WSSubscription::new(&schema);
     let pool = mysql.into_inner();
     let redis_client = redis.into_inner();
+    let mail_service = mail.into_inner();
+    let blob_store = blob_store.into_inner();
+    let auth_service = auth.get_ref().clone();
+    // The loaded roles are kept when Redis is unavailable
+    auth_service.reload_if_changed(&redis_client).ok();
+    let request_info = RequestInfo::from_request(&req);
     let actor = actor.init_context_data(move |payload| {
-        let token = token_from_value(&payload);
-        let user = token
-            .and_then(|token| decode_token(&token))
-            .unwrap_or(SlimUser::default());
+        let principal =
+            token_from_value(&payload).map_or(Principal::Anonymous, Principal::from_token);
         let mut data = Data::default();
-        let ctx = Context::new(user, pool.clone(), redis_client.clone());
+        let ctx = Context::new(
+            principal,
+            pool.clone(),
+            redis_client.clone(),
+            mail_service.clone(),
+            blob_store.clone(),
+            auth_service.clone(),
+            request_info.clone(),
+        );
         data.insert(ctx);
         Ok(data)
     });
@@ -51,3 +95,140 @@ pub async fn gql_playground() -> Result<HttpResponse> {
         .content_type("text/html; charset=utf-8")
         .body(playground_source("/", Some("/"))))
 }
+
+/// The public keys that verify the tokens issued by this API
+pub async fn jwks() -> HttpResponse {
+    HttpResponse::Ok().json(KEYS.jwks())
+}
+
+#[derive(Deserialize)]
+pub struct DataExportQuery {
+    token: String,
+}
+
+/// Downloads the archive of a data export, the signed token of the link replaces the authentication
+pub async fn data_export(
+    path: web::Path<String>,
+    query: web::Query<DataExportQuery>,
+    mysql: web::Data<DbPool>,
+) -> HttpResponse {
+    let export_id = path.into_inner();
+    if decode_data_export_token(&query.token).as_ref() != Some(&export_id) {
+        return HttpResponse::Forbidden().finish();
+    }
+    let export = match mysql.get() {
+        Ok(conn) => DataExport::find(&export_id, &conn),
+        Err(_) => return HttpResponse::InternalServerError().finish(),
+    };
+    match export {
+        Ok(Some(export)) if export.is_downloadable() => {
+            match std::fs::read(archive_path(&export.id)) {
+                Ok(archive) => HttpResponse::Ok()
+                    .content_type("application/json")
+                    .header(
+                        header::CONTENT_DISPOSITION,
+                        format!("attachment; filename=\"data-export-{}.json\"", export.id),
+                    )
+                    .body(archive),
+                Err(_) => HttpResponse::NotFound().finish(),
+            }
+        }
+        Ok(_) => HttpResponse::NotFound().finish(),
+        Err(_) => HttpResponse::InternalServerError().finish(),
+    }
+}
+
+const OAUTH_TOKEN_THROTTLE: &str = "oauth_token";
+
+#[derive(Deserialize)]
+pub struct TokenRequest {
+    grant_type: String,
+    client_id: Option<String>,
+    client_secret: Option<String>,
+    scope: Option<String>,
+}
+
+/// An error response of the OAuth2 token endpoint, as in RFC 6749 section 5.2
+fn oauth_error(status: StatusCode, error: &str) -> HttpResponse {
+    HttpResponse::build(status)
+        .header(header::CACHE_CONTROL, "no-store")
+        .json(json!({ "error": error }))
+}
+
+/// The client credentials sent with HTTP Basic authentication
+fn basic_credentials(req: &HttpRequest) -> Option<(String, String)> {
+    let value = req.headers().get(header::AUTHORIZATION)?.to_str().ok()?;
+    if !value.starts_with("Basic ") {
+        return None;
+    }
+    let decoded = String::from_utf8(base64::decode(value[6..].trim()).ok()?).ok()?;
+    let mut parts = decoded.splitn(2, ':');
+    Some((parts.next()?.to_string(), parts.next()?.to_string()))
+}
+
+/// Issues the access tokens of the OAuth2 clients, only the `client_credentials` grant is supported
+pub async fn oauth_token(
+    req: HttpRequest,
+    form: web::Form<TokenRequest>,
+    mysql: web::Data<DbPool>,
+    redis: web::Data<redis::Client>,
+) -> HttpResponse {
+    let form = form.into_inner();
+    if form.grant_type != "client_credentials" {
+        return oauth_error(StatusCode::BAD_REQUEST, "unsupported_grant_type");
+    }
+    let credentials = basic_credentials(&req).or(match (form.client_id, form.client_secret) {
+        (Some(client_id), Some(client_secret)) => Some((client_id, client_secret)),
+        _ => None,
+    });
+    let (client_id, client_secret) = match credentials {
+        Some(credentials) => credentials,
+        None => return oauth_error(StatusCode::BAD_REQUEST, "invalid_request"),
+    };
+    let request_info = RequestInfo::from_request(&req);
+    let mut subjects = vec![Subject::Account(&client_id)];
+    if let Some(ip) = &request_info.ip {
+        subjects.push(Subject::Ip(ip));
+    }
+    let throttle = Throttle::new(redis.get_ref(), OAUTH_TOKEN_THROTTLE);
+    match throttle.check(&subjects) {
+        Ok(()) => {}
+        Err(SrvError::TooManyAttempts(retry_after)) => {
+            return HttpResponse::TooManyRequests()
+                .header(header::RETRY_AFTER, retry_after.to_string())
+                .json(json!({ "error": "slow_down" }))
+        }
+        Err(_) => return HttpResponse::InternalServerError().finish(),
+    }
+    let client = match mysql.get() {
+        Ok(conn) => OAuthClient::find_active(&client_id, &conn),
+        Err(_) => return HttpResponse::InternalServerError().finish(),
+    };
+    let client = match client {
+        Ok(Some(client)) if client.check_secret(&client_secret) => client,
+        Ok(_) => {
+            throttle.register_failure(&subjects).ok();
+            return oauth_error(StatusCode::UNAUTHORIZED, "invalid_client");
+        }
+        Err(_) => return HttpResponse::InternalServerError().finish(),
+    };
+    throttle.reset(&[Subject::Account(&client_id)]).ok();
+    // Without a scope parameter the token gets every scope allowed for the client
+    let allowed_scopes = client.allowed_scopes();
+    let scopes = match form.scope.as_deref().map(Scope::parse_list) {
+        None => allowed_scopes,
+        Some(Some(scopes)) if scopes.iter().all(|scope| allowed_scopes.contains(scope)) => scopes,
+        Some(_) => return oauth_error(StatusCode::BAD_REQUEST, "invalid_scope"),
+    };
+    match create_client_token(&client.id, &scopes) {
+        Some(access_token) => HttpResponse::Ok()
+            .header(header::CACHE_CONTROL, "no-store")
+            .json(json!({
+                "access_token": access_token,
+                "token_type": "Bearer",
+                "expires_in": CLIENT_TOKEN_DURATION_IN_MINUTES * 60,
+                "scope": Scope::join(&scopes),
+            })),
+        None => HttpResponse::InternalServerError().finish(),
+    }
+}
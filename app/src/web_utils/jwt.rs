@@ -1,14 +1,29 @@
 use crate::models::user::SlimUser;
+use crate::models::{Scope, PERSONAL_ACCESS_TOKEN_PREFIX};
 use crate::utils::env::ENV;
+use crate::web_utils::signing_keys::KEYS;
 use actix_web::{dev, http::header, Error, FromRequest, HttpRequest, HttpResponse};
-use chrono::{Duration, Local};
+use chrono::{Duration, Local, NaiveDateTime};
 use futures::future::{ok, Ready};
-use jsonwebtoken::{decode, encode, Algorithm, DecodingKey, EncodingKey, Header, Validation};
+use jsonwebtoken::{Algorithm, Validation};
 use serde_json::Value;
 use std::convert::From;
+use uuid::Uuid;
 
 pub type LoggedUser = SlimUser;
 
+const AUTH_SUBJECT: &str = "auth";
+const EMAIL_VERIFICATION_SUBJECT: &str = "verify_email";
+const MFA_CHALLENGE_SUBJECT: &str = "mfa_challenge";
+const DATA_EXPORT_SUBJECT: &str = "data_export";
+const CLIENT_SUBJECT: &str = "client";
+const LOGIN_LINK_SUBJECT: &str = "login_link";
+pub const LOGIN_LINK_DURATION_IN_MINUTES: i64 = 15;
+pub const CLIENT_TOKEN_DURATION_IN_MINUTES: i64 = 60;
+pub const MFA_CHALLENGE_DURATION_IN_MINUTES: i64 = 5;
+pub const AUTH_TOKEN_DURATION_IN_HOURS: i64 = 24;
+pub const IMPERSONATION_DURATION_IN_MINUTES: i64 = 30;
+
 #[derive(Debug, Serialize, Deserialize)]
 struct Claims {
     // issuer
@@ -21,49 +36,249 @@ struct Claims {
     exp: i64,
     // user uuid
     id: String,
+    // token id, tokens issued in the same second must still have different digests
+    #[serde(default)]
+    jti: String,
+    // session id, the `user_tokens` row of the login
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    sid: Option<String>,
+    // actor, the admin impersonating the User of `id`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    act: Option<String>,
 }
 
 impl Claims {
-    fn with_id(id: &str) -> Self {
+    fn for_session(id: &str, session_id: &str) -> Self {
+        Self {
+            sid: Some(session_id.to_string()),
+            ..Self::for_subject(
+                id,
+                AUTH_SUBJECT,
+                Duration::hours(AUTH_TOKEN_DURATION_IN_HOURS),
+            )
+        }
+    }
+
+    fn for_subject(id: &str, subject: &str, duration: Duration) -> Self {
         Claims {
             iss: ENV.domain.clone(),
-            sub: "auth".into(),
+            sub: subject.into(),
             id: id.to_string(),
             iat: Local::now().timestamp(),
-            exp: (Local::now() + Duration::hours(24)).timestamp(),
+            exp: (Local::now() + duration).timestamp(),
+            jti: Uuid::new_v4().to_string(),
+            sid: None,
+            act: None,
         }
     }
 }
 
+fn encode_claims<T: serde::Serialize>(claims: &T) -> Option<String> {
+    KEYS.encode(claims)
+}
+
 impl From<Claims> for SlimUser {
     fn from(claims: Claims) -> Self {
         SlimUser {
             id: Some(claims.id),
+            session_id: claims.sid,
             token: None,
+            actor_id: claims.act,
         }
     }
 }
 
-pub fn create_token(id: &str) -> Option<String> {
-    let claims = Claims::with_id(id);
-    let mut header = Header::default();
-    header.alg = Algorithm::HS512;
-    encode(
-        &header,
-        &claims,
-        &EncodingKey::from_secret(ENV.jwt_private_key.as_ref()),
-    )
-    .ok()
+/// Creates the access token of a session, it is verified without the database until it expires
+pub fn create_token(id: &str, session_id: &str) -> Option<String> {
+    encode_claims(&Claims::for_session(id, session_id))
+}
+
+/// Creates the access token of an admin impersonating a User, it can't be refreshed
+///
+/// The `impersonation_id` takes the place of the session, so the token is revoked like one
+pub fn create_impersonation_token(
+    id: &str,
+    impersonation_id: &str,
+    actor_id: &str,
+) -> Option<String> {
+    encode_claims(&Claims {
+        sid: Some(impersonation_id.to_string()),
+        act: Some(actor_id.to_string()),
+        ..Claims::for_subject(
+            id,
+            AUTH_SUBJECT,
+            Duration::minutes(IMPERSONATION_DURATION_IN_MINUTES),
+        )
+    })
 }
 
 pub fn decode_token(token: &str) -> Option<SlimUser> {
-    decode::<Claims>(
-        token,
-        &DecodingKey::from_secret(ENV.jwt_private_key.as_ref()),
-        &Validation::new(Algorithm::HS512),
-    )
-    .map(|data| data.claims.into())
-    .ok()
+    KEYS.decode::<Claims>(token, validation_for(AUTH_SUBJECT))
+        .map(|claims| claims.into())
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct EmailVerificationClaims {
+    iss: String,
+    sub: String,
+    iat: i64,
+    exp: i64,
+    // user uuid
+    id: String,
+    // the E-mail being verified
+    email: String,
+}
+
+/// Creates a signed token that verifies the E-mail of a User
+pub fn create_email_verification_token(id: &str, email: &str) -> Option<String> {
+    let claims = EmailVerificationClaims {
+        iss: ENV.domain.clone(),
+        sub: EMAIL_VERIFICATION_SUBJECT.into(),
+        iat: Local::now().timestamp(),
+        exp: (Local::now() + Duration::hours(48)).timestamp(),
+        id: id.to_string(),
+        email: email.to_string(),
+    };
+    encode_claims(&claims)
+}
+
+/// Decodes an E-mail verification token returning the User id and the verified E-mail
+pub fn decode_email_verification_token(token: &str) -> Option<(String, String)> {
+    KEYS.decode::<EmailVerificationClaims>(token, validation_for(EMAIL_VERIFICATION_SUBJECT))
+        .map(|claims| (claims.id, claims.email))
+}
+
+/// Creates the short lived challenge that a User with Two-Factor Authentication exchanges for a Token
+pub fn create_mfa_challenge_token(id: &str) -> Option<String> {
+    encode_claims(&Claims::for_subject(
+        id,
+        MFA_CHALLENGE_SUBJECT,
+        Duration::minutes(MFA_CHALLENGE_DURATION_IN_MINUTES),
+    ))
+}
+
+/// Decodes a Two-Factor Authentication challenge returning the User id
+pub fn decode_mfa_challenge_token(token: &str) -> Option<String> {
+    KEYS.decode::<Claims>(token, validation_for(MFA_CHALLENGE_SUBJECT))
+        .map(|claims| claims.id)
+}
+
+/// Creates the token of a data export download link, it expires together with the archive
+pub fn create_data_export_token(export_id: &str, expires_at: NaiveDateTime) -> Option<String> {
+    encode_claims(&Claims {
+        exp: expires_at.timestamp(),
+        ..Claims::for_subject(export_id, DATA_EXPORT_SUBJECT, Duration::zero())
+    })
+}
+
+/// Decodes a data export download token returning the export id
+pub fn decode_data_export_token(token: &str) -> Option<String> {
+    KEYS.decode::<Claims>(token, validation_for(DATA_EXPORT_SUBJECT))
+        .map(|claims| claims.id)
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct LoginLinkClaims {
+    iss: String,
+    sub: String,
+    iat: i64,
+    exp: i64,
+    // the E-mail that receives the link, its User may not exist yet
+    email: String,
+    // token id, a link is refused once it was used
+    jti: String,
+}
+
+/// Creates the token of a passwordless login link
+pub fn create_login_link_token(email: &str, token_id: &str) -> Option<String> {
+    encode_claims(&LoginLinkClaims {
+        iss: ENV.domain.clone(),
+        sub: LOGIN_LINK_SUBJECT.into(),
+        iat: Local::now().timestamp(),
+        exp: (Local::now() + Duration::minutes(LOGIN_LINK_DURATION_IN_MINUTES)).timestamp(),
+        email: email.to_string(),
+        jti: token_id.to_string(),
+    })
+}
+
+/// Decodes a login link token returning the E-mail and the token id
+pub fn decode_login_link_token(token: &str) -> Option<(String, String)> {
+    KEYS.decode::<LoginLinkClaims>(token, validation_for(LOGIN_LINK_SUBJECT))
+        .map(|claims| (claims.email, claims.jti))
+}
+
+/// An OAuth2 client authenticated by the `client_credentials` grant
+#[derive(Debug, Clone)]
+pub struct ClientPrincipal {
+    pub client_id: String,
+    pub scopes: Vec<Scope>,
+}
+
+/// Who sent a request, known from the token claims only
+#[derive(Debug)]
+pub enum Principal {
+    User(LoggedUser),
+    Client(ClientPrincipal),
+    /// A personal access token, its User is only known after a database lookup
+    AccessToken(String),
+    Anonymous,
+}
+
+impl Principal {
+    pub fn from_token(token: String) -> Principal {
+        if token.starts_with(PERSONAL_ACCESS_TOKEN_PREFIX) {
+            return Principal::AccessToken(token);
+        }
+        if let Some(mut user) = decode_token(&token) {
+            user.token = Some(token);
+            return Principal::User(user);
+        }
+        decode_client_token(&token).map_or(Principal::Anonymous, Principal::Client)
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct ClientClaims {
+    iss: String,
+    sub: String,
+    iat: i64,
+    exp: i64,
+    client_id: String,
+    // space separated scopes, as in the OAuth2 token response
+    scope: String,
+}
+
+/// Creates the access token of an OAuth2 client
+pub fn create_client_token(client_id: &str, scopes: &[Scope]) -> Option<String> {
+    encode_claims(&ClientClaims {
+        iss: ENV.domain.clone(),
+        sub: CLIENT_SUBJECT.into(),
+        iat: Local::now().timestamp(),
+        exp: (Local::now() + Duration::minutes(CLIENT_TOKEN_DURATION_IN_MINUTES)).timestamp(),
+        client_id: client_id.to_string(),
+        scope: Scope::join(scopes),
+    })
+}
+
+pub fn decode_client_token(token: &str) -> Option<ClientPrincipal> {
+    let claims = KEYS.decode::<ClientClaims>(token, validation_for(CLIENT_SUBJECT))?;
+    Some(ClientPrincipal {
+        client_id: claims.client_id,
+        // Scopes removed from this API are ignored instead of refusing the token
+        scopes: claims
+            .scope
+            .split_whitespace()
+            .filter_map(Scope::from_name)
+            .collect(),
+    })
+}
+
+/// Tokens with other purposes are signed by the same key, so the subject must always be checked
+fn validation_for(subject: &str) -> Validation {
+    // The algorithm is replaced by the one of the key that signed the token
+    let mut validation = Validation::new(Algorithm::HS512);
+    validation.sub = Some(subject.to_string());
+    validation
 }
 
 pub fn token_from_value(value: &Value) -> Option<String> {
@@ -82,7 +297,7 @@ pub fn token_from_value(value: &Value) -> Option<String> {
     }
 }
 
-impl FromRequest for SlimUser {
+impl FromRequest for Principal {
     type Error = Error;
     type Future = Ready<Result<Self, Self::Error>>;
     type Config = ();
@@ -96,15 +311,6 @@ impl FromRequest for SlimUser {
             },
             None => None,
         };
-        match token {
-            None => return ok(SlimUser::default()),
-            Some(token) => {
-                let user = decode_token(&token).map_or(SlimUser::default(), move |mut user| {
-                    user.token = Some(token);
-                    user
-                });
-                ok(user)
-            }
-        }
+        ok(token.map_or(Principal::Anonymous, Principal::from_token))
     }
 }
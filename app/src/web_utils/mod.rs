@@ -1,2 +1,7 @@
 pub mod handlers;
 pub mod jwt;
+pub mod providers;
+pub mod request;
+pub mod signing_keys;
+#[cfg(test)]
+pub mod test_keys;
@@ -0,0 +1,98 @@
+use crate::errors::SrvError;
+use crate::models::UserProvider;
+use crate::utils::env::ENV;
+use crate::web_utils::providers::{claim_is_true, jwks::JwksSource, ExternalUserInfo};
+use jsonwebtoken::{Algorithm, Validation};
+use serde_json::Value;
+
+const APPLE_ISSUER: &str = "https://appleid.apple.com";
+
+#[derive(Debug, Deserialize)]
+struct AppleClaims {
+    sub: String,
+    email: Option<String>,
+    email_verified: Option<Value>,
+    is_private_email: Option<Value>,
+}
+
+/// Verifies a Sign in with Apple identity token against the configured Apple keys
+///
+/// Apple may only share the E-mail on the first sign in and it can be a private relay address,
+/// so these accounts are never linked to an existing User by E-mail
+pub async fn verify_identity_token(token: &str) -> Result<ExternalUserInfo, SrvError> {
+    if ENV.apple_client_ids.is_empty() {
+        return Err(SrvError::Unavailable);
+    }
+    let mut validation = Validation::new(Algorithm::RS256);
+    validation.set_audience(&ENV.apple_client_ids);
+    validation.set_issuer(&[APPLE_ISSUER]);
+    let claims: AppleClaims = JwksSource::from_config(&ENV.apple_jwks_source)
+        .decode(token, validation)
+        .await?;
+    Ok(user_info(claims))
+}
+
+fn user_info(claims: AppleClaims) -> ExternalUserInfo {
+    // Private relay addresses are always verified by Apple
+    let email_verified =
+        claim_is_true(&claims.email_verified) || claim_is_true(&claims.is_private_email);
+    ExternalUserInfo {
+        provider: UserProvider::Apple,
+        external_id: claims.sub,
+        email: claims.email,
+        email_verified,
+        link_by_email: false,
+        // The name is only given to the app on the first authorization, never in the token
+        name: None,
+        locale: None,
+        picture: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn user(value: Value) -> ExternalUserInfo {
+        user_info(serde_json::from_value(value).unwrap())
+    }
+
+    #[test]
+    fn private_relay_addresses_are_verified() {
+        let info = user(json!({
+            "sub": "000123.abc",
+            "email": "xyz@privaterelay.appleid.com",
+            "is_private_email": "true",
+        }));
+        assert_eq!(info.external_id, "000123.abc");
+        assert!(info.email_verified);
+    }
+
+    #[test]
+    fn the_email_verified_claim_can_be_a_string() {
+        let verified = user(json!({
+            "sub": "000123.abc",
+            "email": "user@example.com",
+            "email_verified": "true",
+        }));
+        assert!(verified.email_verified);
+        let unverified = user(json!({
+            "sub": "000123.abc",
+            "email": "user@example.com",
+            "email_verified": false,
+        }));
+        assert!(!unverified.email_verified);
+        assert!(!user(json!({ "sub": "000123.abc" })).email_verified);
+    }
+
+    #[test]
+    fn accounts_are_never_linked_by_email() {
+        let info = user(json!({
+            "sub": "000123.abc",
+            "email": "user@example.com",
+            "email_verified": true,
+        }));
+        assert!(!info.link_by_email);
+    }
+}
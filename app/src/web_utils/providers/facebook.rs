@@ -0,0 +1,138 @@
+use crate::errors::{SrvError, UnauthorizedInfo};
+use crate::models::UserProvider;
+use crate::utils::env::ENV;
+use crate::web_utils::providers::{ExternalUserInfo, HTTP_CLIENT};
+
+#[derive(Debug, Deserialize)]
+struct DebugTokenResponse {
+    data: DebugTokenData,
+}
+
+#[derive(Debug, Deserialize)]
+struct DebugTokenData {
+    app_id: Option<String>,
+    is_valid: bool,
+    user_id: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct FacebookUser {
+    id: String,
+    email: Option<String>,
+    name: Option<String>,
+    picture: Option<FacebookPicture>,
+}
+
+#[derive(Debug, Deserialize)]
+struct FacebookPicture {
+    data: FacebookPictureData,
+}
+
+#[derive(Debug, Deserialize)]
+struct FacebookPictureData {
+    url: Option<String>,
+    #[serde(default)]
+    is_silhouette: bool,
+}
+
+/// Validates a Facebook User Access Token through the Graph API `debug_token` endpoint
+/// and reads the User `id`, `email`, `name` and `picture` from `/me`
+pub async fn verify_access_token(token: &str) -> Result<ExternalUserInfo, SrvError> {
+    if ENV.facebook_app_id.is_empty() || ENV.facebook_app_secret.is_empty() {
+        return Err(SrvError::Unavailable);
+    }
+    let graph_url = ENV.facebook_graph_url.trim_end_matches('/');
+    let app_token = format!("{}|{}", ENV.facebook_app_id, ENV.facebook_app_secret);
+    let DebugTokenResponse { data } = HTTP_CLIENT
+        .get(&format!("{}/debug_token", graph_url))
+        .query(&[("input_token", token), ("access_token", app_token.as_str())])
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await?;
+    if !data.is_valid || data.app_id.as_ref() != Some(&ENV.facebook_app_id) {
+        return Err(invalid_token());
+    }
+    let user: FacebookUser = HTTP_CLIENT
+        .get(&format!("{}/me", graph_url))
+        .query(&[
+            ("fields", "id,email,name,picture.type(large)"),
+            ("access_token", token),
+        ])
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await?;
+    if data.user_id.as_ref() != Some(&user.id) {
+        return Err(invalid_token());
+    }
+    Ok(user_info(user))
+}
+
+fn user_info(user: FacebookUser) -> ExternalUserInfo {
+    ExternalUserInfo {
+        provider: UserProvider::Facebook,
+        external_id: user.id,
+        // The Graph API doesn't assert that the E-mail was verified, so it can't be trusted
+        // to verify the E-mail or to take over an existing account with the same E-mail
+        email_verified: false,
+        email: user.email,
+        link_by_email: false,
+        name: user.name,
+        locale: None,
+        // The default silhouette is not a real avatar
+        picture: user
+            .picture
+            .map(|picture| picture.data)
+            .filter(|data| !data.is_silhouette)
+            .and_then(|data| data.url),
+    }
+}
+
+fn invalid_token() -> SrvError {
+    SrvError::Unauthorized(UnauthorizedInfo {
+        data: String::from("Invalid External Token!"),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn user(value: serde_json::Value) -> ExternalUserInfo {
+        user_info(serde_json::from_value(value).unwrap())
+    }
+
+    #[test]
+    fn the_email_is_never_trusted() {
+        let info = user(json!({
+            "id": "10001",
+            "email": "victim@example.com",
+            "name": "Some User",
+        }));
+        assert_eq!(info.external_id, "10001");
+        assert_eq!(info.email.as_deref(), Some("victim@example.com"));
+        assert!(!info.email_verified);
+        assert!(!info.link_by_email);
+    }
+
+    #[test]
+    fn the_default_silhouette_is_not_an_avatar() {
+        let picture = user(json!({
+            "id": "10001",
+            "picture": { "data": { "url": "https://example.com/me.jpg", "is_silhouette": false } },
+        }))
+        .picture;
+        assert_eq!(picture.as_deref(), Some("https://example.com/me.jpg"));
+        let silhouette = user(json!({
+            "id": "10001",
+            "picture": { "data": { "url": "https://example.com/default.jpg", "is_silhouette": true } },
+        }))
+        .picture;
+        assert!(silhouette.is_none());
+        assert!(user(json!({ "id": "10001" })).picture.is_none());
+    }
+}
@@ -0,0 +1,47 @@
+use crate::errors::{SrvError, UnauthorizedInfo};
+use crate::models::UserProvider;
+use crate::utils::env::ENV;
+use crate::web_utils::providers::{claim_is_true, jwks::JwksSource, ExternalUserInfo};
+use jsonwebtoken::{Algorithm, Validation};
+use serde_json::Value;
+
+const GOOGLE_ISSUERS: [&str; 2] = ["accounts.google.com", "https://accounts.google.com"];
+
+#[derive(Debug, Deserialize)]
+struct GoogleClaims {
+    iss: String,
+    sub: String,
+    email: Option<String>,
+    email_verified: Option<Value>,
+    name: Option<String>,
+    locale: Option<String>,
+    picture: Option<String>,
+}
+
+/// Verifies a Google ID Token, checking its signature against the configured JWKS
+/// and the `aud`, `iss` and `exp` claims
+pub async fn verify_id_token(token: &str) -> Result<ExternalUserInfo, SrvError> {
+    if ENV.google_client_ids.is_empty() {
+        return Err(SrvError::Unavailable);
+    }
+    let mut validation = Validation::new(Algorithm::RS256);
+    validation.set_audience(&ENV.google_client_ids);
+    let claims: GoogleClaims = JwksSource::from_config(&ENV.google_jwks_source)
+        .decode(token, validation)
+        .await?;
+    if !GOOGLE_ISSUERS.contains(&claims.iss.as_str()) {
+        return Err(SrvError::Unauthorized(UnauthorizedInfo {
+            data: String::from("Invalid External Token!"),
+        }));
+    }
+    Ok(ExternalUserInfo {
+        provider: UserProvider::Google,
+        external_id: claims.sub,
+        email_verified: claim_is_true(&claims.email_verified),
+        email: claims.email,
+        link_by_email: true,
+        name: claims.name,
+        locale: claims.locale,
+        picture: claims.picture,
+    })
+}
@@ -0,0 +1,243 @@
+use crate::errors::{SrvError, UnauthorizedInfo};
+use crate::web_utils::providers::HTTP_CLIENT;
+use jsonwebtoken::{decode, decode_header, Algorithm, DecodingKey, Validation};
+use serde::de::DeserializeOwned;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::RwLock;
+use std::time::{Duration, Instant};
+
+const JWKS_CACHE_DURATION: Duration = Duration::from_secs(3600);
+
+lazy_static! {
+    static ref JWKS_CACHE: RwLock<HashMap<String, (Instant, JwkSet)>> = RwLock::new(HashMap::new());
+}
+
+/// Where a JSON Web Key Set is read from, a remote URL or a local file
+#[derive(Debug, Clone, PartialEq)]
+pub enum JwksSource {
+    Url(String),
+    File(PathBuf),
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct JwkSet {
+    pub keys: Vec<Jwk>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Jwk {
+    pub kid: Option<String>,
+    pub kty: String,
+    pub alg: Option<String>,
+    pub n: Option<String>,
+    pub e: Option<String>,
+}
+
+impl JwkSet {
+    fn find(&self, kid: &Option<String>) -> Option<&Jwk> {
+        self.keys
+            .iter()
+            .find(|key| key.kty == "RSA" && (kid.is_none() || &key.kid == kid))
+    }
+}
+
+impl JwksSource {
+    /// Parses a configuration value, anything that is not a http(s) URL is a file path
+    pub fn from_config(value: &str) -> Self {
+        if value.starts_with("http://") || value.starts_with("https://") {
+            JwksSource::Url(value.to_string())
+        } else {
+            JwksSource::File(PathBuf::from(value.trim_start_matches("file://")))
+        }
+    }
+
+    fn cache_key(&self) -> String {
+        match self {
+            JwksSource::Url(url) => url.clone(),
+            JwksSource::File(path) => path.to_string_lossy().to_string(),
+        }
+    }
+
+    async fn fetch(&self) -> Result<JwkSet, SrvError> {
+        match self {
+            JwksSource::Url(url) => Ok(HTTP_CLIENT
+                .get(url.as_str())
+                .send()
+                .await?
+                .error_for_status()?
+                .json::<JwkSet>()
+                .await?),
+            JwksSource::File(path) => {
+                let content = std::fs::read_to_string(path).map_err(|e| {
+                    println!("{:?}", e);
+                    SrvError::InternalServerError
+                })?;
+                serde_json::from_str(&content).map_err(|e| {
+                    println!("{:?}", e);
+                    SrvError::InternalServerError
+                })
+            }
+        }
+    }
+
+    /// Returns the Key Set from the cache, fetching it again when it expired or when `refresh` is set
+    async fn key_set(&self, refresh: bool) -> Result<JwkSet, SrvError> {
+        if !refresh {
+            if let Some(key_set) = self.cached() {
+                return Ok(key_set);
+            }
+        }
+        let key_set = self.fetch().await?;
+        JWKS_CACHE
+            .write()
+            .unwrap()
+            .insert(self.cache_key(), (Instant::now(), key_set.clone()));
+        Ok(key_set)
+    }
+
+    fn cached(&self) -> Option<JwkSet> {
+        let cache = JWKS_CACHE.read().unwrap();
+        match cache.get(&self.cache_key()) {
+            Some((fetched_at, key_set)) if fetched_at.elapsed() < JWKS_CACHE_DURATION => {
+                Some(key_set.clone())
+            }
+            _ => None,
+        }
+    }
+
+    /// Decodes a RS256 signed token verifying the signature against this Key Set
+    ///
+    /// # Arguments
+    /// * `token` - The encoded JWT
+    /// * `validation` - The claims validation, the algorithm is always RS256
+    pub async fn decode<T: DeserializeOwned>(
+        &self,
+        token: &str,
+        mut validation: Validation,
+    ) -> Result<T, SrvError> {
+        let header = decode_header(token).map_err(|_| invalid_token())?;
+        if header.alg != Algorithm::RS256 {
+            return Err(invalid_token());
+        }
+        let key = match self.key_set(false).await?.find(&header.kid) {
+            Some(key) => key.clone(),
+            // The provider may have rotated its keys since we cached them
+            None => self
+                .key_set(true)
+                .await?
+                .find(&header.kid)
+                .cloned()
+                .ok_or_else(invalid_token)?,
+        };
+        let (n, e) = match (&key.n, &key.e) {
+            (Some(n), Some(e)) => (n, e),
+            _ => return Err(invalid_token()),
+        };
+        validation.algorithms = vec![Algorithm::RS256];
+        let key = DecodingKey::from_rsa_components(n, e).map_err(|_| invalid_token())?;
+        decode::<T>(token, &key, &validation)
+            .map(|data| data.claims)
+            .map_err(|_| invalid_token())
+    }
+}
+
+fn invalid_token() -> SrvError {
+    SrvError::Unauthorized(UnauthorizedInfo {
+        data: String::from("Invalid External Token!"),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::web_utils::test_keys::{TestClaims, RSA_MODULUS, RSA_PRIVATE_KEY};
+    use jsonwebtoken::{encode, EncodingKey, Header};
+    use serde_json::{json, Value};
+    use std::path::Path;
+    use uuid::Uuid;
+
+    fn token(kid: &str) -> String {
+        let mut header = Header::new(Algorithm::RS256);
+        header.kid = Some(kid.to_string());
+        encode(
+            &header,
+            &TestClaims::new("external-id"),
+            &EncodingKey::from_rsa_pem(RSA_PRIVATE_KEY.as_bytes()).unwrap(),
+        )
+        .unwrap()
+    }
+
+    fn rsa_key(kid: &str) -> Value {
+        json!({ "kty": "RSA", "use": "sig", "alg": "RS256", "kid": kid, "n": RSA_MODULUS, "e": "AQAB" })
+    }
+
+    fn write_key_set(path: &Path, keys: Vec<Value>) {
+        std::fs::write(path, json!({ "keys": keys }).to_string()).unwrap();
+    }
+
+    async fn verify(source: &JwksSource, token: &str) -> Option<TestClaims> {
+        source
+            .decode(token, Validation::new(Algorithm::RS256))
+            .await
+            .ok()
+    }
+
+    #[test]
+    fn sources_are_urls_or_file_paths() {
+        assert_eq!(
+            JwksSource::from_config("https://www.googleapis.com/oauth2/v3/certs"),
+            JwksSource::Url(String::from("https://www.googleapis.com/oauth2/v3/certs"))
+        );
+        assert_eq!(
+            JwksSource::from_config("file:///etc/jwks.json"),
+            JwksSource::File(PathBuf::from("/etc/jwks.json"))
+        );
+        assert_eq!(
+            JwksSource::from_config("fixtures/jwks.json"),
+            JwksSource::File(PathBuf::from("fixtures/jwks.json"))
+        );
+    }
+
+    #[actix_rt::test]
+    async fn rotated_keys_are_fetched_again() {
+        let path = std::env::temp_dir().join(format!("jwks-{}.json", Uuid::new_v4()));
+        let source = JwksSource::File(path.clone());
+        write_key_set(&path, vec![rsa_key("first")]);
+        let first = verify(&source, &token("first")).await;
+
+        // The provider replaces its keys, the unknown kid refreshes the cached Key Set
+        write_key_set(&path, vec![rsa_key("second")]);
+        let second = verify(&source, &token("second")).await;
+        let removed = verify(&source, &token("first")).await;
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(
+            first.map(|claims| claims.sub).as_deref(),
+            Some("external-id")
+        );
+        assert_eq!(
+            second.map(|claims| claims.sub).as_deref(),
+            Some("external-id")
+        );
+        assert!(removed.is_none());
+    }
+
+    #[actix_rt::test]
+    async fn keys_without_rsa_components_are_not_used() {
+        let path = std::env::temp_dir().join(format!("jwks-{}.json", Uuid::new_v4()));
+        let source = JwksSource::File(path.clone());
+        write_key_set(
+            &path,
+            vec![
+                json!({ "kty": "EC", "kid": "ec", "crv": "P-256", "x": RSA_MODULUS, "y": RSA_MODULUS }),
+                json!({ "kty": "RSA", "kid": "no-modulus", "e": "AQAB" }),
+            ],
+        );
+        let ec = verify(&source, &token("ec")).await;
+        let no_modulus = verify(&source, &token("no-modulus")).await;
+        std::fs::remove_file(&path).unwrap();
+        assert!(ec.is_none());
+        assert!(no_modulus.is_none());
+    }
+}
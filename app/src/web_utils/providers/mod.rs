@@ -0,0 +1,142 @@
+pub mod apple;
+pub mod facebook;
+pub mod google;
+pub mod jwks;
+
+use crate::errors::SrvError;
+use crate::models::{UpdatedUserProfileModel as UpdatedUserProfile, UserProvider};
+use crate::utils::profile::{validate_avatar_url, validate_locale, DISPLAY_NAME_MAX_LENGTH};
+use serde_json::Value;
+
+lazy_static! {
+    pub static ref HTTP_CLIENT: reqwest::Client = reqwest::Client::new();
+}
+
+/// The User information that an External Provider asserted for a verified token
+#[derive(Debug, Clone)]
+pub struct ExternalUserInfo {
+    pub provider: UserProvider,
+    pub external_id: String,
+    pub email: Option<String>,
+    pub email_verified: bool,
+    /// When an existing local account with the same verified E-mail can be linked
+    pub link_by_email: bool,
+    pub name: Option<String>,
+    pub locale: Option<String>,
+    pub picture: Option<String>,
+}
+
+impl ExternalUserInfo {
+    /// The profile shared by the provider, values that don't follow the profile rules are dropped
+    pub fn profile(&self) -> UpdatedUserProfile {
+        let name = self
+            .name
+            .as_ref()
+            .map(|name| {
+                name.trim()
+                    .chars()
+                    .take(DISPLAY_NAME_MAX_LENGTH)
+                    .collect::<String>()
+            })
+            .filter(|name| !name.is_empty());
+        let locale = self
+            .locale
+            .as_ref()
+            // Some providers send `pt_BR` instead of `pt-BR`
+            .map(|locale| locale.replace('_', "-"))
+            .filter(|locale| !locale.is_empty() && validate_locale(locale).is_ok());
+        let picture = self
+            .picture
+            .clone()
+            .filter(|picture| !picture.is_empty() && validate_avatar_url(picture).is_ok());
+        UpdatedUserProfile {
+            display_name: name.map(Some),
+            locale: locale.map(Some),
+            timezone: None,
+            avatar_url: picture.map(Some),
+            avatar_key: None,
+        }
+    }
+}
+
+/// Some providers send boolean claims as strings, so `"true"` and `true` are both accepted
+pub fn claim_is_true(value: &Option<Value>) -> bool {
+    match value {
+        Some(Value::Bool(value)) => *value,
+        Some(Value::String(value)) => value == "true",
+        _ => false,
+    }
+}
+
+/// Verifies a token issued by the given External Provider
+pub async fn verify_token(
+    provider: UserProvider,
+    token: &str,
+) -> Result<ExternalUserInfo, SrvError> {
+    match provider {
+        UserProvider::Facebook => facebook::verify_access_token(token).await,
+        UserProvider::Google => google::verify_id_token(token).await,
+        UserProvider::Apple => apple::verify_identity_token(token).await,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn boolean_claims_can_be_strings() {
+        assert!(claim_is_true(&Some(json!(true))));
+        assert!(claim_is_true(&Some(json!("true"))));
+        assert!(!claim_is_true(&Some(json!(false))));
+        assert!(!claim_is_true(&Some(json!("false"))));
+        assert!(!claim_is_true(&Some(json!("yes"))));
+        assert!(!claim_is_true(&Some(json!(1))));
+        assert!(!claim_is_true(&None));
+    }
+
+    fn info(name: &str, locale: &str, picture: &str) -> ExternalUserInfo {
+        ExternalUserInfo {
+            provider: UserProvider::Google,
+            external_id: String::from("external-id"),
+            email: None,
+            email_verified: false,
+            link_by_email: false,
+            name: Some(name.to_string()),
+            locale: Some(locale.to_string()),
+            picture: Some(picture.to_string()),
+        }
+    }
+
+    #[test]
+    fn the_profile_follows_the_profile_rules() {
+        let profile = info("  Some User ", "pt_BR", "https://example.com/me.jpg").profile();
+        assert_eq!(profile.display_name, Some(Some(String::from("Some User"))));
+        assert_eq!(profile.locale, Some(Some(String::from("pt-BR"))));
+        assert_eq!(
+            profile.avatar_url,
+            Some(Some(String::from("https://example.com/me.jpg")))
+        );
+        assert_eq!(profile.timezone, None);
+    }
+
+    #[test]
+    fn invalid_profile_values_are_dropped() {
+        let profile = info("   ", "not a locale", "javascript:alert(1)").profile();
+        assert_eq!(profile.display_name, None);
+        assert_eq!(profile.locale, None);
+        assert_eq!(profile.avatar_url, None);
+        let long_name = "a".repeat(DISPLAY_NAME_MAX_LENGTH + 10);
+        let profile = info(&long_name, "", "").profile();
+        assert_eq!(
+            profile
+                .display_name
+                .flatten()
+                .map(|name| name.chars().count()),
+            Some(DISPLAY_NAME_MAX_LENGTH)
+        );
+        assert_eq!(profile.locale, None);
+        assert_eq!(profile.avatar_url, None);
+    }
+}
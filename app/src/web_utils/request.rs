@@ -0,0 +1,92 @@
+use crate::utils::env::ENV;
+use actix_web::http::header::USER_AGENT;
+use actix_web::HttpRequest;
+use std::net::SocketAddr;
+
+/// Information about the HTTP request that started an operation
+#[derive(Debug, Clone, Default)]
+pub struct RequestInfo {
+    pub ip: Option<String>,
+    pub user_agent: Option<String>,
+}
+
+// Longer values are cut to fit the `user_tokens.user_agent` column
+const MAX_USER_AGENT_LENGTH: usize = 512;
+
+const X_FORWARDED_FOR: &str = "x-forwarded-for";
+
+impl RequestInfo {
+    pub fn from_request(req: &HttpRequest) -> Self {
+        let forwarded_for = req
+            .headers()
+            .get(X_FORWARDED_FOR)
+            .and_then(|value| value.to_str().ok());
+        Self {
+            ip: req
+                .peer_addr()
+                .map(|peer| client_ip(peer, forwarded_for, &ENV.trusted_proxies)),
+            user_agent: req
+                .headers()
+                .get(USER_AGENT)
+                .and_then(|value| value.to_str().ok())
+                .map(|value| value.chars().take(MAX_USER_AGENT_LENGTH).collect()),
+        }
+    }
+}
+
+/// The address of the peer, unless it is a trusted proxy. Every proxy appends the address that
+/// connected to it to `X-Forwarded-For`, so the last address that isn't a trusted proxy is the
+/// client, the ones before it are sent by the client and can be forged
+fn client_ip(peer: SocketAddr, forwarded_for: Option<&str>, trusted_proxies: &[String]) -> String {
+    let peer = peer.ip().to_string();
+    if !trusted_proxies.contains(&peer) {
+        return peer;
+    }
+    forwarded_for
+        .unwrap_or_default()
+        .rsplit(',')
+        .map(|address| without_port(address.trim()))
+        .filter(|address| !address.is_empty())
+        .find(|address| !trusted_proxies.contains(address))
+        .unwrap_or(peer)
+}
+
+/// The forwarded address may come with the port of the peer
+fn without_port(address: &str) -> String {
+    address
+        .parse::<SocketAddr>()
+        .map(|address| address.ip().to_string())
+        .unwrap_or_else(|_| address.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn peer(ip: &str) -> SocketAddr {
+        format!("{}:40000", ip).parse().unwrap()
+    }
+
+    #[test]
+    fn forwarded_addresses_are_ignored_without_a_trusted_proxy() {
+        let trusted = vec![String::from("10.0.0.1")];
+        assert_eq!(
+            client_ip(peer("203.0.113.7"), Some("198.51.100.1"), &trusted),
+            "203.0.113.7"
+        );
+    }
+
+    #[test]
+    fn the_last_untrusted_forwarded_address_is_the_client() {
+        let trusted = vec![String::from("10.0.0.1"), String::from("10.0.0.2")];
+        assert_eq!(
+            client_ip(
+                peer("10.0.0.1"),
+                Some("198.51.100.1, 203.0.113.7, 10.0.0.2"),
+                &trusted
+            ),
+            "203.0.113.7"
+        );
+        assert_eq!(client_ip(peer("10.0.0.1"), None, &trusted), "10.0.0.1");
+    }
+}
@@ -0,0 +1,431 @@
+//! Keys that sign and verify the tokens issued by this API
+use crate::utils::env::{DEFAULT_JWT_PRIVATE_KEY, ENV};
+use jsonwebtoken::{
+    decode, decode_header, encode, Algorithm, DecodingKey, EncodingKey, Header, Validation,
+};
+use serde::de::DeserializeOwned;
+use serde_json::{json, Value};
+use simple_asn1::{from_der, ASN1Block};
+
+lazy_static! {
+    pub static ref KEYS: KeyRing =
+        KeyRing::from_env().unwrap_or_else(|error| panic!("Invalid JWT keys: {}", error));
+}
+
+/// An asymmetric key identified by the `kid` header of the tokens
+struct SigningKey {
+    kid: String,
+    algorithm: Algorithm,
+    // Keys being rotated out only verify the tokens they signed
+    encoding: Option<EncodingKey>,
+    decoding: DecodingKey,
+    jwk: Value,
+}
+
+/// The shared secret used before the asymmetric keys, tokens signed with it have no `kid`
+struct LegacyKey {
+    encoding: EncodingKey,
+    decoding: DecodingKey,
+}
+
+pub struct KeyRing {
+    keys: Vec<SigningKey>,
+    signing: Option<usize>,
+    legacy: Option<LegacyKey>,
+}
+
+impl KeyRing {
+    /// Loads the keys of `JWT_KEYS`, every entry is `kid:algorithm:public_pem_path[:private_pem_path]`
+    ///
+    /// Without asymmetric keys the tokens are signed with `JWT_PRIVATE_KEY` using HS512,
+    /// its default value is refused outside of `dev`
+    fn from_env() -> Result<KeyRing, String> {
+        let keys = ENV
+            .jwt_keys
+            .iter()
+            .map(|entry| SigningKey::from_config(entry))
+            .collect::<Result<Vec<_>, _>>()?;
+        let signing = if ENV.jwt_signing_kid.is_empty() {
+            keys.iter().position(|key| key.encoding.is_some())
+        } else {
+            let position = keys
+                .iter()
+                .position(|key| key.kid == ENV.jwt_signing_kid)
+                .ok_or_else(|| {
+                    format!("JWT_SIGNING_KID {} is not in JWT_KEYS", ENV.jwt_signing_kid)
+                })?;
+            if keys[position].encoding.is_none() {
+                return Err(format!(
+                    "the key {} has no private key",
+                    ENV.jwt_signing_kid
+                ));
+            }
+            Some(position)
+        };
+        let default_secret = ENV.jwt_private_key == DEFAULT_JWT_PRIVATE_KEY;
+        let legacy = if default_secret && ENV.rust_env != "dev" {
+            None
+        } else {
+            Some(LegacyKey {
+                encoding: EncodingKey::from_secret(ENV.jwt_private_key.as_ref()),
+                decoding: DecodingKey::from_secret(ENV.jwt_private_key.as_ref()),
+            })
+        };
+        if signing.is_none() && legacy.is_none() {
+            return Err(String::from(
+                "set JWT_KEYS or change the default JWT_PRIVATE_KEY outside of dev",
+            ));
+        }
+        Ok(KeyRing {
+            keys,
+            signing,
+            legacy,
+        })
+    }
+
+    /// Signs the claims with the current signing key
+    pub fn encode<T: serde::Serialize>(&self, claims: &T) -> Option<String> {
+        match (self.signing, &self.legacy) {
+            (Some(position), _) => {
+                let key = &self.keys[position];
+                let mut header = Header::new(key.algorithm);
+                header.kid = Some(key.kid.clone());
+                encode(&header, claims, key.encoding.as_ref()?).ok()
+            }
+            (None, Some(legacy)) => {
+                encode(&Header::new(Algorithm::HS512), claims, &legacy.encoding).ok()
+            }
+            (None, None) => None,
+        }
+    }
+
+    /// Verifies a token with the key of its `kid`, the algorithm is always the one of the key
+    pub fn decode<T: DeserializeOwned>(
+        &self,
+        token: &str,
+        mut validation: Validation,
+    ) -> Option<T> {
+        let header = decode_header(token).ok()?;
+        let (algorithm, key) = match &header.kid {
+            Some(kid) => {
+                let key = self.keys.iter().find(|key| &key.kid == kid)?;
+                (key.algorithm, &key.decoding)
+            }
+            None => (Algorithm::HS512, &self.legacy.as_ref()?.decoding),
+        };
+        validation.algorithms = vec![algorithm];
+        decode::<T>(token, key, &validation)
+            .map(|data| data.claims)
+            .ok()
+    }
+
+    /// The public keys as a JSON Web Key Set, so other services can verify the tokens
+    pub fn jwks(&self) -> Value {
+        json!({ "keys": self.keys.iter().map(|key| key.jwk.clone()).collect::<Vec<_>>() })
+    }
+}
+
+impl SigningKey {
+    fn from_config(entry: &str) -> Result<SigningKey, String> {
+        let parts: Vec<&str> = entry.split(':').collect();
+        let (kid, algorithm, public_path, private_path) = match parts.as_slice() {
+            [kid, algorithm, public_path] => (kid, algorithm, public_path, None),
+            [kid, algorithm, public_path, private_path] => {
+                (kid, algorithm, public_path, Some(private_path))
+            }
+            _ => return Err(format!("invalid JWT_KEYS entry {}", entry)),
+        };
+        let algorithm = match *algorithm {
+            "RS256" => Algorithm::RS256,
+            "EdDSA" => Algorithm::EdDSA,
+            _ => {
+                return Err(format!(
+                    "unsupported algorithm {} of the key {}",
+                    algorithm, kid
+                ))
+            }
+        };
+        let public_pem = read_pem(public_path)?;
+        let private_pem = match private_path {
+            Some(private_path) => Some(read_pem(private_path)?),
+            None => None,
+        };
+        SigningKey::new(kid, algorithm, &public_pem, private_pem.as_deref())
+    }
+
+    fn new(
+        kid: &str,
+        algorithm: Algorithm,
+        public_pem: &[u8],
+        private_pem: Option<&[u8]>,
+    ) -> Result<SigningKey, String> {
+        let decoding = match algorithm {
+            Algorithm::RS256 => DecodingKey::from_rsa_pem(public_pem),
+            _ => DecodingKey::from_ed_pem(public_pem),
+        }
+        .map_err(|error| format!("invalid public key of {}: {}", kid, error))?;
+        let encoding = match private_pem {
+            Some(private_pem) => Some(
+                match algorithm {
+                    Algorithm::RS256 => EncodingKey::from_rsa_pem(private_pem),
+                    _ => EncodingKey::from_ed_pem(private_pem),
+                }
+                .map_err(|error| format!("invalid private key of {}: {}", kid, error))?,
+            ),
+            None => None,
+        };
+        let jwk = public_jwk(kid, algorithm, public_pem)?;
+        Ok(SigningKey {
+            kid: kid.to_string(),
+            algorithm,
+            encoding,
+            decoding,
+            jwk,
+        })
+    }
+}
+
+fn read_pem(path: &str) -> Result<Vec<u8>, String> {
+    std::fs::read(path).map_err(|error| format!("can't read {}: {}", path, error))
+}
+
+/// Builds the JWK of a `PUBLIC KEY` PEM, the SubjectPublicKeyInfo holds the raw key in a bit string
+fn public_jwk(kid: &str, algorithm: Algorithm, public_pem: &[u8]) -> Result<Value, String> {
+    let invalid = || format!("the public key of {} must be a PUBLIC KEY PEM", kid);
+    let pem = pem::parse(public_pem).map_err(|_| invalid())?;
+    let public_key = match from_der(&pem.contents).map_err(|_| invalid())?.as_slice() {
+        [ASN1Block::Sequence(_, blocks)] => match blocks.as_slice() {
+            [_, ASN1Block::BitString(_, _, public_key)] => public_key.clone(),
+            _ => return Err(invalid()),
+        },
+        _ => return Err(invalid()),
+    };
+    match algorithm {
+        Algorithm::RS256 => {
+            let (n, e) = match from_der(&public_key).map_err(|_| invalid())?.as_slice() {
+                [ASN1Block::Sequence(_, blocks)] => match blocks.as_slice() {
+                    [ASN1Block::Integer(_, n), ASN1Block::Integer(_, e)] => {
+                        (n.to_bytes_be().1, e.to_bytes_be().1)
+                    }
+                    _ => return Err(invalid()),
+                },
+                _ => return Err(invalid()),
+            };
+            Ok(json!({
+                "kty": "RSA",
+                "use": "sig",
+                "alg": "RS256",
+                "kid": kid,
+                "n": base64_url(&n),
+                "e": base64_url(&e),
+            }))
+        }
+        _ => Ok(json!({
+            "kty": "OKP",
+            "use": "sig",
+            "alg": "EdDSA",
+            "crv": "Ed25519",
+            "kid": kid,
+            "x": base64_url(&public_key),
+        })),
+    }
+}
+
+fn base64_url(bytes: &[u8]) -> String {
+    base64::encode_config(bytes, base64::URL_SAFE_NO_PAD)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::web_utils::test_keys::{
+        TestClaims, NEW_PRIVATE_KEY, NEW_PUBLIC_KEY, OLD_PRIVATE_KEY, OLD_PUBLIC_KEY, RSA_MODULUS,
+        RSA_PUBLIC_KEY,
+    };
+
+    const LEGACY_SECRET: &[u8] = b"legacy secret";
+
+    fn key(kid: &str, public_pem: &str, private_pem: Option<&str>) -> SigningKey {
+        SigningKey::new(
+            kid,
+            Algorithm::EdDSA,
+            public_pem.as_bytes(),
+            private_pem.map(str::as_bytes),
+        )
+        .unwrap()
+    }
+
+    fn legacy() -> Option<LegacyKey> {
+        Some(LegacyKey {
+            encoding: EncodingKey::from_secret(LEGACY_SECRET),
+            decoding: DecodingKey::from_secret(LEGACY_SECRET),
+        })
+    }
+
+    #[test]
+    fn tokens_of_the_previous_key_are_verified_after_a_rotation() {
+        let before = KeyRing {
+            keys: vec![
+                key("old", OLD_PUBLIC_KEY, Some(OLD_PRIVATE_KEY)),
+                key("new", NEW_PUBLIC_KEY, Some(NEW_PRIVATE_KEY)),
+            ],
+            signing: Some(0),
+            legacy: None,
+        };
+        let old_token = before.encode(&TestClaims::new("user")).unwrap();
+        assert_eq!(
+            decode_header(&old_token).unwrap().kid.as_deref(),
+            Some("old")
+        );
+
+        // The old key is kept without its private key until its tokens expire
+        let after = KeyRing {
+            keys: vec![
+                key("old", OLD_PUBLIC_KEY, None),
+                key("new", NEW_PUBLIC_KEY, Some(NEW_PRIVATE_KEY)),
+            ],
+            signing: Some(1),
+            legacy: None,
+        };
+        let new_token = after.encode(&TestClaims::new("user")).unwrap();
+        assert_eq!(
+            decode_header(&new_token).unwrap().kid.as_deref(),
+            Some("new")
+        );
+        let decoded: Option<TestClaims> = after.decode(&old_token, Validation::default());
+        assert_eq!(decoded.map(|claims| claims.sub).as_deref(), Some("user"));
+        let decoded: Option<TestClaims> = after.decode(&new_token, Validation::default());
+        assert_eq!(decoded.map(|claims| claims.sub).as_deref(), Some("user"));
+
+        // Once the old key is removed its tokens are refused
+        let removed = KeyRing {
+            keys: vec![key("new", NEW_PUBLIC_KEY, Some(NEW_PRIVATE_KEY))],
+            signing: Some(0),
+            legacy: None,
+        };
+        let decoded: Option<TestClaims> = removed.decode(&old_token, Validation::default());
+        assert!(decoded.is_none());
+    }
+
+    #[test]
+    fn tokens_signed_by_another_key_are_refused() {
+        let ring = KeyRing {
+            keys: vec![key("new", NEW_PUBLIC_KEY, Some(NEW_PRIVATE_KEY))],
+            signing: Some(0),
+            legacy: None,
+        };
+        let mut header = Header::new(Algorithm::EdDSA);
+        header.kid = Some(String::from("new"));
+        let forged = encode(
+            &header,
+            &TestClaims::new("user"),
+            &EncodingKey::from_ed_pem(OLD_PRIVATE_KEY.as_bytes()).unwrap(),
+        )
+        .unwrap();
+        let decoded: Option<TestClaims> = ring.decode(&forged, Validation::default());
+        assert!(decoded.is_none());
+    }
+
+    #[test]
+    fn the_algorithm_of_the_header_is_ignored() {
+        let ring = KeyRing {
+            keys: vec![key("new", NEW_PUBLIC_KEY, Some(NEW_PRIVATE_KEY))],
+            signing: Some(0),
+            legacy: legacy(),
+        };
+        // A HMAC token keyed by the public key must not pass as a token of that key
+        let mut header = Header::new(Algorithm::HS256);
+        header.kid = Some(String::from("new"));
+        let forged = encode(
+            &header,
+            &TestClaims::new("user"),
+            &EncodingKey::from_secret(NEW_PUBLIC_KEY.as_bytes()),
+        )
+        .unwrap();
+        let decoded: Option<TestClaims> = ring.decode(&forged, Validation::default());
+        assert!(decoded.is_none());
+    }
+
+    #[test]
+    fn tokens_without_kid_need_the_legacy_key() {
+        let legacy_token = encode(
+            &Header::new(Algorithm::HS512),
+            &TestClaims::new("user"),
+            &EncodingKey::from_secret(LEGACY_SECRET),
+        )
+        .unwrap();
+        let with_legacy = KeyRing {
+            keys: vec![key("new", NEW_PUBLIC_KEY, Some(NEW_PRIVATE_KEY))],
+            signing: Some(0),
+            legacy: legacy(),
+        };
+        let decoded: Option<TestClaims> = with_legacy.decode(&legacy_token, Validation::default());
+        assert!(decoded.is_some());
+        let without_legacy = KeyRing {
+            keys: vec![key("new", NEW_PUBLIC_KEY, Some(NEW_PRIVATE_KEY))],
+            signing: Some(0),
+            legacy: None,
+        };
+        let decoded: Option<TestClaims> =
+            without_legacy.decode(&legacy_token, Validation::default());
+        assert!(decoded.is_none());
+    }
+
+    #[test]
+    fn only_the_legacy_key_signs_without_asymmetric_keys() {
+        let ring = KeyRing {
+            keys: vec![],
+            signing: None,
+            legacy: legacy(),
+        };
+        let token = ring.encode(&TestClaims::new("user")).unwrap();
+        let header = decode_header(&token).unwrap();
+        assert_eq!(header.alg, Algorithm::HS512);
+        assert!(header.kid.is_none());
+    }
+
+    #[test]
+    fn the_jwks_has_the_public_keys() {
+        let ring = KeyRing {
+            keys: vec![
+                key("old", OLD_PUBLIC_KEY, None),
+                key("new", NEW_PUBLIC_KEY, Some(NEW_PRIVATE_KEY)),
+            ],
+            signing: Some(1),
+            legacy: None,
+        };
+        assert_eq!(
+            ring.jwks(),
+            json!({ "keys": [
+                {
+                    "kty": "OKP",
+                    "use": "sig",
+                    "alg": "EdDSA",
+                    "crv": "Ed25519",
+                    "kid": "old",
+                    "x": "ahD1hQp-OC9b8m9xVYz6Qb-_epcyizGda7TxZBgIPBo",
+                },
+                {
+                    "kty": "OKP",
+                    "use": "sig",
+                    "alg": "EdDSA",
+                    "crv": "Ed25519",
+                    "kid": "new",
+                    "x": "08v4YrLoRmhWg97Dc7Oxex6gCDqAi6raLUobWRkMuXQ",
+                },
+            ]})
+        );
+        assert_eq!(
+            public_jwk("rsa", Algorithm::RS256, RSA_PUBLIC_KEY.as_bytes()).unwrap(),
+            json!({
+                "kty": "RSA",
+                "use": "sig",
+                "alg": "RS256",
+                "kid": "rsa",
+                "n": RSA_MODULUS,
+                "e": "AQAB",
+            })
+        );
+        assert!(public_jwk("rsa", Algorithm::RS256, OLD_PRIVATE_KEY.as_bytes()).is_err());
+    }
+}